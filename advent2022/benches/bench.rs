@@ -1,4 +1,4 @@
-use advent2022::*;
+use advent::*;
 use macros::benchmark_days;
 
 benchmark_days!(1, 8);
@@ -0,0 +1,40 @@
+use std::fmt::Write;
+
+use advent::day7;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// A single chain of directories `depth` deep, each holding one file of `size`
+fn deep_transcript(depth: usize, size: usize) -> String {
+    let mut transcript = String::from("$ cd /\n");
+    for i in 0..depth {
+        write!(transcript, "$ ls\ndir d{i}\n{size} f{i}.txt\n$ cd d{i}\n").unwrap();
+    }
+    transcript
+}
+
+fn bench_deep_transcript(c: &mut Criterion) {
+    let transcript = deep_transcript(2_000, 25_000);
+
+    c.bench_function("Day 7 deep P1", |b| {
+        b.iter(|| day7::solve_part1(&transcript))
+    });
+    c.bench_function("Day 7 deep P2", |b| {
+        b.iter(|| day7::solve_part2(&transcript))
+    });
+    c.bench_function("Day 7 deep du", |b| {
+        b.iter(|| day7::FileSystem::from_transcript(&transcript)?.du(None))
+    });
+
+    // Deep enough that anything quadratic in the depth stands out
+    let transcript = deep_transcript(20_000, 2_500);
+
+    c.bench_function("Day 7 deeper P1", |b| {
+        b.iter(|| day7::solve_part1(&transcript))
+    });
+    c.bench_function("Day 7 deeper P2", |b| {
+        b.iter(|| day7::solve_part2(&transcript))
+    });
+}
+
+criterion_group!(benches, bench_deep_transcript);
+criterion_main!(benches);
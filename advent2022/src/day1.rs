@@ -1,27 +1,68 @@
-use std::cmp::Reverse;
+use std::{cmp::Reverse, collections::BinaryHeap, io::BufRead};
 
 use anyhow::{Context, Result};
-use itertools::{process_results, Itertools};
-
-fn for_each_chunk<R>(f: fn(&mut dyn Iterator<Item = u64>) -> R) -> Result<R> {
-    process_results(
-        include_str!("inputs/day1.txt").lines().map(|v| {
-            if v.is_empty() {
-                Ok(None)
-            } else {
-                v.parse::<u64>()
-                    .context("Failed to parse line as calorie count")
-                    .map(Some)
-            }
-        }),
-        |iter| f(&mut iter.batching(|it| it.map_while(|it| it).sum1::<u64>())),
-    )
+
+use crate::utils::read_records;
+
+pub const INPUT: &str = include_str!("inputs/day1.txt");
+
+/// An elf by position in the list, counting from 0, and the calories it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elf {
+    pub index: usize,
+    pub calories: u64,
+}
+
+/// The `k` elves carrying the most calories, most first, read one elf at a time so only `k`
+/// elves are ever kept. Elves are separated by blank lines, ties go to the earlier elf.
+pub fn top_k(reader: impl BufRead, k: usize) -> Result<Vec<Elf>> {
+    // Min-heap on (calories, earliest index), so the root is the first to be pushed out
+    let mut top = BinaryHeap::new();
+    for (index, record) in read_records(reader).enumerate() {
+        let (start, record) = record.context("Failed to read calorie log")?;
+        let calories = record
+            .lines()
+            .zip(start..)
+            .map(|(line, line_number)| {
+                let line = line.trim();
+                line.parse::<u64>().with_context(|| {
+                    format!("Line {line_number}: failed to parse {line:?} as calorie count")
+                })
+            })
+            .sum::<Result<u64>>()?;
+
+        top.push(Reverse((calories, Reverse(index))));
+        if top.len() > k {
+            top.pop();
+        }
+    }
+
+    let mut top = top
+        .into_iter()
+        .map(|Reverse((calories, Reverse(index)))| Elf { index, calories })
+        .collect::<Vec<_>>();
+    top.sort_by_key(|elf| (Reverse(elf.calories), elf.index));
+
+    Ok(top)
+}
+
+pub fn solve_part1(input: &str) -> Result<u64> {
+    let top = top_k(input.as_bytes(), 1)?;
+    let elf = top.first().context("Puzzle input is empty")?;
+
+    Ok(elf.calories)
+}
+
+pub fn solve_part2(input: &str) -> Result<u64> {
+    let top = top_k(input.as_bytes(), 3)?;
+
+    Ok(top.iter().map(|elf| elf.calories).sum())
 }
 
 pub fn part1() -> Result<u64> {
-    for_each_chunk(|it| it.max().expect("Puzzle input is not empty"))
+    solve_part1(INPUT)
 }
 
 pub fn part2() -> Result<u64> {
-    for_each_chunk(|it| it.map(Reverse).k_smallest(3).map(|x| x.0).sum())
+    solve_part2(INPUT)
 }
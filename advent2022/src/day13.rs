@@ -1,12 +1,25 @@
-use std::{cmp::Ordering, fmt, iter::once};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter, Write},
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use itertools::Itertools;
-use serde::Deserialize;
+use nom::{
+    branch::alt,
+    character::complete::{char, u64},
+    combinator::{all_consuming, map},
+    multi::separated_list0,
+    sequence::delimited,
+    Finish, IResult,
+};
 
-#[derive(Deserialize, Clone, PartialEq, Eq)]
-#[serde(untagged)]
-enum Entry {
+use crate::utils::records;
+
+pub const INPUT: &str = include_str!("inputs/day13.txt");
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Entry {
     Number(u64),
     List(Vec<Entry>),
 }
@@ -21,7 +34,7 @@ impl Entry {
 }
 
 impl fmt::Debug for Entry {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Entry::Number(n) => write!(f, "{n}"),
             Entry::List(n) => f.debug_list().entries(n).finish(),
@@ -29,6 +42,16 @@ impl fmt::Debug for Entry {
     }
 }
 
+/// Prints the packet exactly as it is written in the puzzle input.
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Number(n) => write!(f, "{n}"),
+            Entry::List(entries) => write!(f, "[{}]", entries.iter().format(",")),
+        }
+    }
+}
+
 impl Ord for Entry {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
@@ -44,28 +67,175 @@ impl PartialOrd for Entry {
     }
 }
 
-fn parse_input() -> Result<impl Iterator<Item = (Entry, Entry)>> {
-    let iter = include_str!("inputs/day13.txt")
-        .split("\r\n\r\n")
-        .map(|pairs| {
-            pairs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// What settled a comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Numbers(u64, u64),
+    LeftRanOut,
+    RightRanOut,
+    /// Nothing did, the packets are equal.
+    Equal,
+}
+
+/// The decision trail of a packet comparison: where in the nested lists it was decided, and
+/// where an integer had to be promoted to a list on the way there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub ordering: Ordering,
+    pub outcome: Outcome,
+    pub path: Vec<usize>,
+    pub promotions: Vec<(Vec<usize>, Side)>,
+}
+
+struct Path<'a>(&'a [usize]);
+
+impl Display for Path<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            [] => write!(f, "top level"),
+            path => path.iter().try_for_each(|i| write!(f, "[{i}]")),
+        }
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let path = Path(&self.path);
+        match self.outcome {
+            Outcome::Numbers(l, r) => write!(f, "{path} {l} vs {r}")?,
+            Outcome::LeftRanOut => write!(f, "{path} left ran out of items")?,
+            Outcome::RightRanOut => write!(f, "{path} right ran out of items")?,
+            Outcome::Equal => write!(f, "packets are equal")?,
+        }
+        for (path, side) in &self.promotions {
+            write!(f, ", {side} integer promoted at {}", Path(path))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Trail {
+    path: Vec<usize>,
+    promotions: Vec<(Vec<usize>, Side)>,
+}
+
+// Mirrors `Entry::cmp`, `None` while the packets are still equal
+fn decide(l: &Entry, r: &Entry, trail: &mut Trail) -> Option<(Ordering, Outcome)> {
+    match (l, r) {
+        (Entry::Number(a), Entry::Number(b)) => {
+            (a != b).then(|| (a.cmp(b), Outcome::Numbers(*a, *b)))
+        }
+        (Entry::List(l), Entry::List(r)) => decide_lists(l, r, trail),
+        (l, r) => {
+            let side = match l {
+                Entry::Number(_) => Side::Left,
+                Entry::List(_) => Side::Right,
+            };
+            trail.promotions.push((trail.path.clone(), side));
+            let decision = l.with_slice(|l| r.with_slice(|r| decide_lists(l, r, trail)));
+            if decision.is_none() {
+                trail.promotions.pop();
+            }
+            decision
+        }
+    }
+}
+
+fn decide_lists(l: &[Entry], r: &[Entry], trail: &mut Trail) -> Option<(Ordering, Outcome)> {
+    for (i, (l, r)) in l.iter().zip(r).enumerate() {
+        trail.path.push(i);
+        if let Some(decision) = decide(l, r, trail) {
+            return Some(decision);
+        }
+        trail.path.pop();
+    }
+
+    match l.len().cmp(&r.len()) {
+        Ordering::Less => Some((Ordering::Less, Outcome::LeftRanOut)),
+        Ordering::Greater => Some((Ordering::Greater, Outcome::RightRanOut)),
+        Ordering::Equal => None,
+    }
+}
+
+/// Compares two packets like `Ord` does, keeping track of why.
+pub fn explain(l: &Entry, r: &Entry) -> Explanation {
+    let mut trail = Trail::default();
+    let (ordering, outcome) = decide(l, r, &mut trail).unwrap_or((Ordering::Equal, Outcome::Equal));
+
+    Explanation {
+        ordering,
+        outcome,
+        path: trail.path,
+        promotions: trail.promotions,
+    }
+}
+
+/// One line per pair saying whether it is in order and why.
+pub fn explain_pairs(input: &str) -> Result<String> {
+    let mut out = String::new();
+    for (i, (l, r)) in parse_input(input)?.iter().enumerate() {
+        let explanation = explain(l, r);
+        let verdict = match explanation.ordering {
+            Ordering::Less => "in order",
+            _ => "out of order",
+        };
+        writeln!(out, "Pair {}: {verdict}, {explanation}", i + 1)?;
+    }
+
+    Ok(out)
+}
+
+fn entry(i: &str) -> IResult<&str, Entry> {
+    alt((
+        map(u64, Entry::Number),
+        map(
+            delimited(char('['), separated_list0(char(','), entry), char(']')),
+            Entry::List,
+        ),
+    ))(i)
+}
+
+pub fn parse_packet(line: &str) -> Result<Entry> {
+    let (_, packet) = all_consuming(entry)(line.trim_end())
+        .map_err(|e| e.to_owned())
+        .finish()?;
+
+    Ok(packet)
+}
+
+fn parse_input(input: &str) -> Result<Vec<(Entry, Entry)>> {
+    records(input)
+        .map(|(line_number, pair)| {
+            let (l, r) = pair
                 .lines()
-                .map(|line| serde_json::from_str::<Entry>(line).unwrap())
+                .map(parse_packet)
                 .collect_tuple()
-                .unwrap()
-        });
-
-    Ok(iter)
+                .with_context(|| format!("Line {line_number}: expected a pair of packets"))?;
+            Ok((l?, r?))
+        })
+        .collect()
 }
 
-pub fn part1() -> Result<usize> {
-    let pairs = parse_input()?;
+pub fn solve_part1(input: &str) -> Result<usize> {
+    let pairs = parse_input(input)?;
     let mut sum = 0;
-    for (i, (l, r)) in pairs.enumerate() {
-        println!("\n== Pair {i} ==");
-        println!("l = {l:?}");
-        println!("r = {r:?}");
-        println!("l < r = {}", l < r);
+    for (i, (l, r)) in pairs.into_iter().enumerate() {
         if l < r {
             sum += i + 1;
         }
@@ -74,23 +244,51 @@ pub fn part1() -> Result<usize> {
     Ok(sum)
 }
 
-pub fn part2() -> Result<usize> {
-    let divider_packets = vec![
-        Entry::List(vec![Entry::Number(2)]),
-        Entry::List(vec![Entry::Number(6)]),
-    ];
+/// Where each of `queries` would land, counting from 1, if they were sorted in with `packets`.
+/// Queries go after packets equal to them, and equal queries keep their order.
+pub fn ranks(packets: &[Entry], queries: &[Entry]) -> Vec<usize> {
+    queries
+        .iter()
+        .enumerate()
+        .map(|(i, query)| {
+            let packets_before = packets.iter().filter(|&p| p <= query).count();
+            let queries_before = queries
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| match other.cmp(query) {
+                    Ordering::Less => true,
+                    Ordering::Equal => j < i,
+                    Ordering::Greater => false,
+                })
+                .count();
+            packets_before + queries_before + 1
+        })
+        .collect()
+}
 
-    let mut packets = parse_input()?
-        .flat_map(|tup| once(tup.0).chain(once(tup.1)))
-        .chain(divider_packets.iter().cloned())
+/// Product of the ranks of the `dividers` among the packets of `input`.
+pub fn decoder_key(input: &str, dividers: &[Entry]) -> Result<usize> {
+    let packets = parse_input(input)?
+        .into_iter()
+        .flat_map(|(l, r)| [l, r])
         .collect::<Vec<_>>();
 
-    packets.sort();
+    Ok(ranks(&packets, dividers).into_iter().product())
+}
 
-    let decoder = divider_packets
-        .iter()
-        .map(|d| packets.binary_search(d).unwrap() + 1)
-        .product::<usize>();
+pub fn solve_part2(input: &str) -> Result<usize> {
+    let divider_packets = [
+        Entry::List(vec![Entry::List(vec![Entry::Number(2)])]),
+        Entry::List(vec![Entry::List(vec![Entry::Number(6)])]),
+    ];
+
+    decoder_key(input, &divider_packets)
+}
 
-    Ok(decoder)
+pub fn part1() -> Result<usize> {
+    solve_part1(INPUT)
+}
+
+pub fn part2() -> Result<usize> {
+    solve_part2(INPUT)
 }
@@ -4,26 +4,25 @@ use anyhow::Result;
 
 use wasm::Grid;
 
-fn parse_input(floor: bool) -> Grid {
-    Grid::parse(include_str!("../inputs/day14.txt"), floor)
+pub const INPUT: &str = include_str!("../inputs/day14.txt");
+
+fn run_sim(input: &str, floor: bool) -> Result<usize> {
+    let mut grid = Grid::parse(input, floor)?;
+    Ok(grid.run_to_completion())
 }
 
-fn run_sim(floor: bool) -> Result<usize> {
-    let mut grid = parse_input(floor);
-    loop {
-        let done = grid.step();
-        if done {
-            break;
-        }
-    }
+pub fn solve_part1(input: &str) -> Result<usize> {
+    run_sim(input, false)
+}
 
-    Ok(grid.num_settled())
+pub fn solve_part2(input: &str) -> Result<usize> {
+    run_sim(input, true)
 }
 
 pub fn part1() -> Result<usize> {
-    run_sim(false)
+    solve_part1(INPUT)
 }
 
 pub fn part2() -> Result<usize> {
-    run_sim(true)
+    solve_part2(INPUT)
 }
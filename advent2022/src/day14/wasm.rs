@@ -1,10 +1,15 @@
 use derive_more::{Add, AddAssign, Sub};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::{prelude::*, Clamped, JsCast};
+#[cfg(feature = "wasm")]
 use web_sys::ImageData;
 
 use std::{fmt, iter};
 
+use anyhow::{bail, ensure, Context, Result};
+
 const SOURCE: Point = Point { x: 500, y: 0 };
+const MAX_CELLS: usize = 1 << 24;
 const AIR_COLOR: [u8; 3] = [0xFF, 0xFF, 0xFF];
 const ROCK_COLOR: [u8; 3] = [0x80, 0x84, 0x87];
 const SAND_COLOR: [u8; 3] = [0xC2, 0xB2, 0x80];
@@ -17,13 +22,22 @@ struct Point {
 }
 
 impl Point {
-    fn parse(s: &str) -> Self {
-        let mut tokens = s.split(',');
-        let (x, y) = (tokens.next().unwrap(), tokens.next().unwrap());
-        Self {
-            x: x.parse().unwrap(),
-            y: y.parse().unwrap(),
-        }
+    fn parse(s: &str) -> Result<Self> {
+        let (x, y) = s
+            .split_once(',')
+            .with_context(|| format!("Expected x,y but found {s:?}"))?;
+        // Small enough that the cave's bounds can't overflow
+        let coordinate = |n: &str| {
+            n.trim()
+                .parse::<i16>()
+                .map(i32::from)
+                .with_context(|| format!("{n:?} is not a coordinate"))
+        };
+
+        Ok(Self {
+            x: coordinate(x)?,
+            y: coordinate(y)?,
+        })
     }
 
     fn signum(self) -> Self {
@@ -40,10 +54,23 @@ struct Polyline {
 }
 
 impl Polyline {
-    fn parse(s: &str) -> Self {
-        Self {
-            points: s.split(" -> ").map(Point::parse).collect(),
+    fn parse(s: &str) -> Result<Self> {
+        let points = s
+            .split("->")
+            .map(Point::parse)
+            .collect::<Result<Vec<_>>>()?;
+        for (a, b) in points.iter().zip(&points[1..]) {
+            ensure!(
+                (a.x == b.x) ^ (a.y == b.y),
+                "{},{} -> {},{} is not a horizontal or vertical line",
+                a.x,
+                a.y,
+                b.x,
+                b.y
+            );
         }
+
+        Ok(Self { points })
     }
 
     fn path_points(&self) -> impl Iterator<Item = Point> + '_ {
@@ -76,7 +103,7 @@ enum Cell {
     Sand,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Grid {
     origin: Point,
     width: usize,
@@ -85,30 +112,39 @@ pub struct Grid {
     settled: usize,
     grains: Vec<Point>,
     floor: bool,
+    done: bool,
+    pixels: Vec<u8>,
 }
 
 impl Grid {
-    pub fn parse(input: &str, floor: bool) -> Self {
-        let mut polylines: Vec<_> = input.lines().map(Polyline::parse).collect();
+    pub fn parse(input: &str, floor: bool) -> Result<Self> {
+        let mut polylines = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| Polyline::parse(line).with_context(|| format!("Line {}", i + 1)))
+            .collect::<Result<Vec<_>>>()?;
         let (mut min_x, mut min_y, mut max_x, mut max_y) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
 
         // Find bounding coordinates
-        let source = Point { x: 500, y: 0 };
         for point in polylines
             .iter()
             .flat_map(|p| p.points.iter())
-            .chain(iter::once(&source))
+            .chain(iter::once(&SOURCE))
         {
             min_x = min_x.min(point.x);
             min_y = min_y.min(point.y);
             max_x = max_x.max(point.x);
             max_y = max_y.max(point.y);
         }
+        // A column either side for sand to fall past the outermost rocks
+        (min_x, max_x) = (min_x - 1, max_x + 1);
 
         if floor {
+            // Sand spreads at most one column per row, so the pile stays this wide
             let floor_y = max_y + 2;
-            min_x = 300;
-            max_x = 700;
+            min_x = min_x.min(SOURCE.x - floor_y - 1);
+            max_x = max_x.max(SOURCE.x + floor_y + 1);
             max_y = floor_y;
             polylines.push(Polyline {
                 points: vec![
@@ -126,8 +162,14 @@ impl Grid {
 
         // Compute dimensions
         let origin = Point { x: min_x, y: min_y };
-        let width: usize = (max_x - min_x + 1).try_into().unwrap();
-        let height: usize = (max_y - min_y + 1).try_into().unwrap();
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        if width
+            .checked_mul(height)
+            .map_or(true, |cells| cells > MAX_CELLS)
+        {
+            bail!("A cave of {width}x{height} is too big to simulate");
+        }
 
         // Make the grid
         let mut grid = Self {
@@ -138,14 +180,16 @@ impl Grid {
             settled: 0,
             grains: vec![],
             floor,
+            done: false,
+            pixels: vec![0xFF; 4 * width * height],
         };
 
         // Place the rocks
         for point in polylines.iter().flat_map(|p| p.path_points()) {
-            *grid.cell_mut(point).unwrap() = Cell::Rock;
+            *grid.cell_mut(point).expect("Bounds include every rock") = Cell::Rock;
         }
 
-        grid
+        Ok(grid)
     }
 
     fn cell_index(&self, point: Point) -> Option<usize> {
@@ -174,6 +218,10 @@ impl Grid {
     }
 
     pub fn step(&mut self) -> bool {
+        if self.done {
+            return true;
+        }
+
         let mut grains = std::mem::take(&mut self.grains);
         let _ = grains
             .drain_filter(|grain| {
@@ -203,23 +251,115 @@ impl Grid {
         self.grains.push(SOURCE);
 
         // Check done conditions
-        if !self.floor {
-            self.grains.iter().any(|p| p.y == (self.height - 1) as i32)
+        let blocked = matches!(self.cell(SOURCE).unwrap(), Cell::Sand);
+        self.done = if !self.floor {
+            blocked || self.grains.iter().any(|p| p.y == (self.height - 1) as i32)
         } else {
-            matches!(self.cell(Point { x: 500, y: 0 }).unwrap(), Cell::Sand)
+            blocked
+        };
+        self.done
+    }
+
+    pub fn reset(&mut self) {
+        for cell in self.cells.iter_mut() {
+            if let Cell::Sand = cell {
+                *cell = Cell::Air;
+            }
+        }
+        self.settled = 0;
+        self.grains.clear();
+        self.done = false;
+    }
+
+    pub fn step_n(&mut self, n: usize) -> bool {
+        for _ in 0..n {
+            if self.step() {
+                break;
+            }
+        }
+        self.done
+    }
+
+    pub fn run_to_completion(&mut self) -> usize {
+        while !self.step() {}
+        self.settled
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// In-flight grains in pixel coordinates, i.e. relative to the top left of the grid.
+    pub fn grains(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.grains.iter().map(|&grain| {
+            let Point { x, y } = grain - self.origin;
+            (x, y)
+        })
+    }
+
+    pub fn num_grains(&self) -> usize {
+        self.grains.len()
+    }
+
+    /// Repaints and returns the RGBA pixel buffer, `4 * width * height` bytes long.
+    pub fn pixels(&mut self) -> &[u8] {
+        self.paint();
+        &self.pixels
+    }
+
+    fn paint(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let point = Point {
+                    x: x as _,
+                    y: y as _,
+                } + self.origin;
+                let color = match self.cell(point).unwrap() {
+                    Cell::Air => &AIR_COLOR,
+                    Cell::Rock => &ROCK_COLOR,
+                    Cell::Sand => &SAND_COLOR,
+                };
+                self.put_pixel(x, y, color);
+            }
         }
+
+        for i in 0..self.grains.len() {
+            let Point { x, y } = self.grains[i] - self.origin;
+            self.put_pixel(x as usize, y as usize, &CURRENT_COLOR);
+        }
+    }
+
+    fn put_pixel(&mut self, x: usize, y: usize, color: &[u8; 3]) {
+        let base_index = 4 * (y * self.width + x);
+        self.pixels[base_index..base_index + 3].copy_from_slice(color);
+        self.pixels[base_index + 3] = 0xFF;
     }
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 impl Grid {
     #[wasm_bindgen(constructor)]
     pub fn new(floor: bool) -> Self {
-        Self::parse(include_str!("../inputs/day14.txt"), floor)
+        Self::parse(super::INPUT, floor).expect("Puzzle input is a valid cave")
+    }
+
+    /// Builds a grid from cave text in the puzzle's `x,y -> x,y` format, throwing if it isn't one.
+    #[wasm_bindgen]
+    pub fn from_input(input: &str, floor: bool) -> Result<Grid, JsError> {
+        Self::parse(input, floor).map_err(|e| JsError::new(&format!("{e:#}")))
     }
 
     #[wasm_bindgen]
-    pub fn render(&self, canvas_id: &str) {
+    pub fn render(&mut self, canvas_id: &str) {
         let document = web_sys::window().unwrap().document().unwrap();
         let canvas = document.get_element_by_id(canvas_id).unwrap();
         let canvas: web_sys::HtmlCanvasElement = canvas
@@ -237,66 +377,81 @@ impl Grid {
             .dyn_into::<web_sys::CanvasRenderingContext2d>()
             .unwrap();
 
-        let mut pixels = vec![0u8; 4 * self.width * self.height];
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let base_index = 4 * (y * self.width + x);
-                pixels[base_index] = 255;
-                pixels[base_index + 1] = 20;
-                pixels[base_index + 2] = 20;
-                pixels[base_index + 3] = 255;
-            }
-        }
-
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let point = Point {
-                    x: x as _,
-                    y: y as _,
-                } + self.origin;
-                let cell = self.cell(point).unwrap();
-                let color = match cell {
-                    Cell::Air => &AIR_COLOR,
-                    Cell::Rock => &ROCK_COLOR,
-                    Cell::Sand => &SAND_COLOR,
-                };
-
-                let base_index = 4 * (y * self.width + x);
-                pixels[base_index] = color[0];
-                pixels[base_index + 1] = color[1];
-                pixels[base_index + 2] = color[2];
-            }
-        }
-
-        for grain in self.grains.iter().copied() {
-            let Point { x, y } = grain - self.origin;
-
-            let color = &CURRENT_COLOR;
-            let base_index = 4 * (y as usize * self.width + x as usize);
-            pixels[base_index] = color[0];
-            pixels[base_index + 1] = color[1];
-            pixels[base_index + 2] = color[2];
-        }
-
+        let width = self.width;
         context
             .put_image_data(
-                &ImageData::new_with_u8_clamped_array(Clamped(&pixels[..]), self.width as _)
-                    .unwrap(),
+                &ImageData::new_with_u8_clamped_array(Clamped(self.pixels()), width as _).unwrap(),
                 0.0,
                 0.0,
             )
             .unwrap();
     }
 
+    /// Repaints the RGBA pixel buffer and returns a pointer to it in wasm memory.
+    ///
+    /// The buffer is `4 * width * height` bytes long, so it can be viewed without
+    /// copying via `new Uint8Array(memory.buffer, ptr, len)`. The view is invalidated
+    /// if the wasm memory grows.
+    #[wasm_bindgen(js_name = pixels)]
+    pub fn js_pixels(&mut self) -> *const u8 {
+        self.pixels().as_ptr()
+    }
+
+    #[wasm_bindgen]
+    pub fn pixels_len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    #[wasm_bindgen(getter = width)]
+    pub fn js_width(&self) -> usize {
+        self.width()
+    }
+
+    #[wasm_bindgen(getter = height)]
+    pub fn js_height(&self) -> usize {
+        self.height()
+    }
+
+    #[wasm_bindgen(getter = done)]
+    pub fn js_done(&self) -> bool {
+        self.is_done()
+    }
+
     #[wasm_bindgen(js_name = step)]
     pub fn js_step(&mut self) -> bool {
         self.step()
     }
 
+    #[wasm_bindgen(js_name = step_n)]
+    pub fn js_step_n(&mut self, n: usize) -> bool {
+        self.step_n(n)
+    }
+
+    #[wasm_bindgen(js_name = reset)]
+    pub fn js_reset(&mut self) {
+        self.reset()
+    }
+
+    #[wasm_bindgen(js_name = run_to_completion)]
+    pub fn js_run_to_completion(&mut self) -> usize {
+        self.run_to_completion()
+    }
+
     #[wasm_bindgen(js_name = num_settled)]
     pub fn js_num_settled(&self) -> usize {
         self.num_settled()
     }
+
+    #[wasm_bindgen(js_name = num_grains)]
+    pub fn js_num_grains(&self) -> usize {
+        self.num_grains()
+    }
+
+    /// In-flight grains as flattened `[x0, y0, x1, y1, ...]` pixel coordinates.
+    #[wasm_bindgen(js_name = grains)]
+    pub fn js_grains(&self) -> Vec<i32> {
+        self.grains().flat_map(|(x, y)| [x, y]).collect()
+    }
 }
 
 impl fmt::Debug for Grid {
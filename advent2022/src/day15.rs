@@ -19,6 +19,8 @@ use nom::{
 
 use crate::utils::InclusiveRangeExt;
 
+pub const INPUT: &str = include_str!("inputs/day15.txt");
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
 struct Point {
     x: i64,
@@ -105,15 +107,17 @@ fn zones(input: &str) -> IResult<&str, Vec<Zone>> {
     ))
 }
 
-fn parse_input() -> Result<Vec<Zone>> {
-    let (_, zones) = all_consuming(zones)(include_str!("inputs/day15.txt")).finish()?;
+fn parse_input(input: &str) -> Result<Vec<Zone>> {
+    let (_, zones) = all_consuming(zones)(input)
+        .map_err(|e| e.to_owned())
+        .finish()?;
 
     Ok(zones)
 }
 
-pub fn part1() -> Result<usize> {
+pub fn solve_part1(input: &str) -> Result<usize> {
     const ROW: i64 = 2000000;
-    let zones = parse_input()?;
+    let zones = parse_input(input)?;
 
     let coverage = zones
         .iter()
@@ -129,9 +133,9 @@ pub fn part1() -> Result<usize> {
     Ok(coverage)
 }
 
-pub fn part2() -> Result<i64> {
+pub fn solve_part2(input: &str) -> Result<i64> {
     const LIMIT: i64 = 4_000_000;
-    let zones = parse_input()?;
+    let zones = parse_input(input)?;
 
     // Get mapping of y to the coverage along y-axis at x of sensor
     let coverages = zones
@@ -173,3 +177,11 @@ pub fn part2() -> Result<i64> {
     // Convert location to tuning frequency
     Ok(x * 4000000 + y)
 }
+
+pub fn part1() -> Result<usize> {
+    solve_part1(INPUT)
+}
+
+pub fn part2() -> Result<i64> {
+    solve_part2(INPUT)
+}
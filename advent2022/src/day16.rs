@@ -5,11 +5,11 @@ use std::{
     iter,
 };
 
-use anyhow::Result;
+use anyhow::{bail, ensure, Context, Result};
 use itertools::Itertools;
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take},
+    bytes::complete::{tag, take_while_m_n},
     character::complete,
     combinator::all_consuming,
     combinator::map,
@@ -18,6 +18,10 @@ use nom::{
     Finish, IResult,
 };
 
+pub const INPUT: &str = include_str!("inputs/day16.txt");
+
+const START: Name = Name(*b"AA");
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
 struct Name([u8; 2]);
 
@@ -36,14 +40,17 @@ impl fmt::Display for Name {
 
 impl Name {
     fn parse(i: &str) -> IResult<&str, Self> {
-        map(take(2usize), |slice: &str| {
-            Self(
-                slice
-                    .as_bytes()
-                    .try_into()
-                    .expect("Took two bytes, cannot fail"),
-            )
-        })(i)
+        map(
+            take_while_m_n(2, 2, |c: char| c.is_ascii_uppercase()),
+            |slice: &str| {
+                Self(
+                    slice
+                        .as_bytes()
+                        .try_into()
+                        .expect("Took two ASCII letters, cannot fail"),
+                )
+            },
+        )(i)
     }
 }
 
@@ -59,7 +66,8 @@ impl Valve {
         map(
             tuple((
                 preceded(tag("Valve "), Name::parse),
-                preceded(tag(" has flow rate="), complete::u64),
+                // Small enough that pressure over 30 minutes can't overflow
+                preceded(tag(" has flow rate="), map(complete::u32, u64::from)),
                 preceded(
                     alt((
                         tag("; tunnels lead to valves "),
@@ -86,22 +94,43 @@ type Path = Vec<(Name, Name)>;
 type Best = HashMap<BTreeSet<Name>, u64>;
 
 impl Network {
-    fn new() -> Self {
+    fn parse(input: &str) -> Result<Self> {
+        let valves = input
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                all_consuming(Valve::parse)(l)
+                    .map_err(|e| e.to_owned())
+                    .finish()
+                    .map(|(_, valve)| valve)
+                    .with_context(|| format!("Line {}", i + 1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         let mut net = Self {
-            valves: include_str!("inputs/day16.txt")
-                .lines()
-                .map(|l| all_consuming(Valve::parse)(l).finish().unwrap().1)
+            valves: valves
+                .into_iter()
                 // start off with zero connections (since we're still parsing)
                 .map(|valve| (valve.name, (valve, Connections::default())))
                 .collect(),
         };
+        ensure!(
+            net.valves.contains_key(&START),
+            "No valve {START} to start from"
+        );
+        for (valve, _) in net.valves.values() {
+            if let Some(link) = valve.links.iter().find(|l| !net.valves.contains_key(l)) {
+                bail!("Valve {} leads to unknown valve {link}", valve.name);
+            }
+        }
+
         let names = net.valves.keys().copied().collect::<Vec<_>>();
         for name in names {
             // fill in the connections as needed
             let conns = net.connections(name);
             net.valves.get_mut(&name).unwrap().1 = conns;
         }
-        net
+        Ok(net)
     }
 
     fn connections(&self, start: Name) -> Connections {
@@ -224,14 +253,14 @@ impl State<'_> {
     }
 }
 
-fn run_simulation() -> (u64, u64) {
+fn run_simulation(input: &str) -> Result<(u64, u64)> {
     let part1_pressure;
     let part2_pressure;
 
-    let net = Network::new();
+    let net = Network::parse(input)?;
     let p1_state = State {
         network: &net,
-        position: Name(*b"AA"),
+        position: START,
         max_turns: 30,
         turn: 0,
         pressure: 0,
@@ -248,7 +277,7 @@ fn run_simulation() -> (u64, u64) {
     // Part 2
     {
         let mut best = Best::default();
-        let mut p2_state = p1_state.clone();
+        let mut p2_state = p1_state;
         p2_state.max_turns = 26;
         p2_state.apply_best_moves(&mut best);
 
@@ -258,18 +287,27 @@ fn run_simulation() -> (u64, u64) {
             .filter(|(human, elephant)| human.0.is_disjoint(elephant.0))
             .map(|(human, elephant)| human.1 + elephant.1)
             .max()
-            .unwrap();
+            // Only the empty set when no valve is worth opening
+            .unwrap_or(0);
 
         part2_pressure = best_pressure;
     }
 
-    (part1_pressure, part2_pressure)
+    Ok((part1_pressure, part2_pressure))
+}
+
+pub fn solve_part1(input: &str) -> Result<u64> {
+    Ok(run_simulation(input)?.0)
+}
+
+pub fn solve_part2(input: &str) -> Result<u64> {
+    Ok(run_simulation(input)?.1)
 }
 
 pub fn part1() -> Result<u64> {
-    Ok(run_simulation().0)
+    solve_part1(INPUT)
 }
 
 pub fn part2() -> Result<u64> {
-    Ok(run_simulation().1)
+    solve_part2(INPUT)
 }
@@ -1,90 +1,405 @@
-use anyhow::{Context, Result};
-use enum_utils::FromStr;
-use itertools::{process_results, Itertools};
+use std::fmt::Write;
 
-use crate::parse;
+use anyhow::{bail, ensure, Context, Result};
+use itertools::Itertools;
 
-#[derive(Debug, FromStr, Copy, Clone)]
-enum Move {
-    #[enumeration(alias = "A", alias = "X")]
-    Rock = 1,
+pub const INPUT: &str = include_str!("inputs/day2.txt");
 
-    #[enumeration(alias = "B", alias = "Y")]
-    Paper = 2,
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+/// A move, by its position in [`Rules`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Shape(pub usize);
 
-    #[enumeration(alias = "C", alias = "Z")]
-    Scissors = 3,
+/// A game of rock-paper-scissors or any of its variants: which shapes there are, which beats
+/// which, how rounds are scored and which letters the strategy guide uses.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    names: Vec<String>,
+    shape_scores: Vec<u32>,
+    // `beats[a][b]`, pairs neither beats are a draw
+    beats: Vec<Vec<bool>>,
+    outcome_scores: [u32; 3],
+    opponent_letters: Vec<char>,
+    response_letters: Vec<char>,
+    outcome_letters: [char; 3],
 }
 
-#[derive(Debug, FromStr, Copy, Clone)]
-enum Outcome {
-    #[enumeration(rename = "Z")]
-    Win = 6,
+impl Rules {
+    /// Shapes with the score for playing them, and the pairs `(winner, loser)` of which beats
+    /// which. The opponent's letters start at `A` and the responses end at `Z`.
+    pub fn new(shapes: &[(&str, u32)], beats: &[(&str, &str)]) -> Result<Self> {
+        ensure!(
+            (1..=13).contains(&shapes.len()),
+            "Expected between 1 and 13 shapes, found {}",
+            shapes.len()
+        );
+        ensure!(
+            shapes.iter().map(|(name, _)| name).all_unique(),
+            "Shape names must be unique"
+        );
 
-    #[enumeration(rename = "Y")]
-    Tie = 3,
+        let names = shapes
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        let find = |name: &str| {
+            names
+                .iter()
+                .position(|n| n == name)
+                .with_context(|| format!("Unknown shape {name:?}"))
+        };
 
-    #[enumeration(rename = "X")]
-    Lose = 0,
-}
+        let mut table = vec![vec![false; shapes.len()]; shapes.len()];
+        for &(winner, loser) in beats {
+            let (w, l) = (find(winner)?, find(loser)?);
+            ensure!(w != l, "{winner} cannot beat itself");
+            ensure!(!table[l][w], "{winner} and {loser} both beat each other");
+            table[w][l] = true;
+        }
 
-impl Move {
-    fn from_requirement(requirement: &Outcome, mov: &Move) -> Move {
-        match (requirement, mov) {
-            (Outcome::Tie, Move::Rock) => Move::Rock,
-            (Outcome::Win, Move::Rock) => Move::Paper,
-            (Outcome::Lose, Move::Rock) => Move::Scissors,
-            (Outcome::Tie, Move::Paper) => Move::Paper,
-            (Outcome::Win, Move::Paper) => Move::Scissors,
-            (Outcome::Lose, Move::Paper) => Move::Rock,
-            (Outcome::Tie, Move::Scissors) => Move::Scissors,
-            (Outcome::Win, Move::Scissors) => Move::Rock,
-            (Outcome::Lose, Move::Scissors) => Move::Paper,
+        let letters = |from: u8| (from..).take(shapes.len()).map(char::from).collect();
+        Ok(Self {
+            shape_scores: shapes.iter().map(|&(_, score)| score).collect(),
+            beats: table,
+            outcome_scores: [0, 3, 6],
+            opponent_letters: letters(b'A'),
+            response_letters: letters(b'Z' + 1 - shapes.len() as u8),
+            outcome_letters: ['X', 'Y', 'Z'],
+            names,
+        })
+    }
+
+    pub fn rock_paper_scissors() -> Self {
+        Self::new(
+            &[("Rock", 1), ("Paper", 2), ("Scissors", 3)],
+            &[
+                ("Rock", "Scissors"),
+                ("Paper", "Rock"),
+                ("Scissors", "Paper"),
+            ],
+        )
+        .expect("Valid rules")
+    }
+
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        Self::new(
+            &[
+                ("Rock", 1),
+                ("Paper", 2),
+                ("Scissors", 3),
+                ("Lizard", 4),
+                ("Spock", 5),
+            ],
+            &[
+                ("Scissors", "Paper"),
+                ("Paper", "Rock"),
+                ("Rock", "Lizard"),
+                ("Lizard", "Spock"),
+                ("Spock", "Scissors"),
+                ("Scissors", "Lizard"),
+                ("Lizard", "Paper"),
+                ("Paper", "Spock"),
+                ("Spock", "Rock"),
+                ("Rock", "Scissors"),
+            ],
+        )
+        .expect("Valid rules")
+    }
+
+    /// Letters for the opponent's shapes and for the responses, in shape order.
+    pub fn with_letters(mut self, opponent: &str, response: &str) -> Result<Self> {
+        for letters in [opponent, response] {
+            ensure!(
+                letters.chars().count() == self.names.len() && letters.chars().all_unique(),
+                "Expected {} distinct letters, found {letters:?}",
+                self.names.len()
+            );
         }
+        self.opponent_letters = opponent.chars().collect();
+        self.response_letters = response.chars().collect();
+        Ok(self)
+    }
+
+    /// Letters asking to lose, draw and win.
+    pub fn with_outcome_letters(mut self, lose: char, draw: char, win: char) -> Self {
+        self.outcome_letters = [lose, draw, win];
+        self
+    }
+
+    /// Points for losing, drawing and winning a round.
+    pub fn with_outcome_scores(mut self, lose: u32, draw: u32, win: u32) -> Self {
+        self.outcome_scores = [lose, draw, win];
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
     }
 
-    fn against(&self, mov: &Move) -> Outcome {
-        match (self, mov) {
-            (Move::Rock, Move::Rock) => Outcome::Tie,
-            (Move::Paper, Move::Rock) => Outcome::Win,
-            (Move::Scissors, Move::Rock) => Outcome::Lose,
-            (Move::Rock, Move::Paper) => Outcome::Lose,
-            (Move::Paper, Move::Paper) => Outcome::Tie,
-            (Move::Scissors, Move::Paper) => Outcome::Win,
-            (Move::Rock, Move::Scissors) => Outcome::Win,
-            (Move::Paper, Move::Scissors) => Outcome::Lose,
-            (Move::Scissors, Move::Scissors) => Outcome::Tie,
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> + Clone {
+        (0..self.names.len()).map(Shape)
+    }
+
+    pub fn name(&self, shape: Shape) -> &str {
+        &self.names[shape.0]
+    }
+
+    pub fn response_letter(&self, shape: Shape) -> char {
+        self.response_letters[shape.0]
+    }
+
+    fn letter<T>(letters: &[char], letter: &str, f: impl Fn(usize) -> T) -> Result<T> {
+        letter
+            .chars()
+            .exactly_one()
+            .ok()
+            .and_then(|letter| letters.iter().position(|&l| l == letter))
+            .map(f)
+            .with_context(|| format!("Unknown letter {letter:?}"))
+    }
+
+    pub fn opponent(&self, letter: &str) -> Result<Shape> {
+        Self::letter(&self.opponent_letters, letter, Shape)
+    }
+
+    pub fn response(&self, letter: &str) -> Result<Shape> {
+        Self::letter(&self.response_letters, letter, Shape)
+    }
+
+    pub fn requirement(&self, letter: &str) -> Result<Outcome> {
+        Self::letter(&self.outcome_letters, letter, |i| {
+            [Outcome::Lose, Outcome::Draw, Outcome::Win][i]
+        })
+    }
+
+    pub fn outcome(&self, mine: Shape, theirs: Shape) -> Outcome {
+        match (self.beats[mine.0][theirs.0], self.beats[theirs.0][mine.0]) {
+            (true, _) => Outcome::Win,
+            (_, true) => Outcome::Lose,
+            _ => Outcome::Draw,
         }
     }
+
+    /// What to play against `theirs` for the `wanted` outcome, worked out from the beats
+    /// relation. When several shapes would do, the one scoring the most is picked.
+    pub fn response_for(&self, theirs: Shape, wanted: Outcome) -> Option<Shape> {
+        self.shapes()
+            .filter(|&mine| self.outcome(mine, theirs) == wanted)
+            .max_by_key(|&mine| self.shape_scores[mine.0])
+    }
+
+    pub fn score(&self, mine: Shape, theirs: Shape) -> u32 {
+        self.shape_scores[mine.0] + self.outcome_scores[self.outcome(mine, theirs) as usize]
+    }
 }
 
-enum Input {
+/// How the second column of the strategy guide is read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// The shape to respond with.
     Guide,
+    /// The outcome to aim for.
     Plan,
+    /// The shape each response letter stands for, in letter order.
+    Assignment(Vec<Shape>),
+}
+
+/// A round of the strategy guide, as played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Round {
+    pub line: usize,
+    pub theirs: Shape,
+    pub mine: Shape,
+    pub outcome: Outcome,
+    pub score: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Tally {
+    pub fn of(rounds: &[Round]) -> Self {
+        let mut tally = Self::default();
+        for round in rounds {
+            match round.outcome {
+                Outcome::Win => tally.wins += 1,
+                Outcome::Draw => tally.draws += 1,
+                Outcome::Lose => tally.losses += 1,
+            }
+        }
+        tally
+    }
+}
+
+fn parse_line(line_number: usize, line: &str) -> Result<(&str, &str)> {
+    line.split_whitespace()
+        .collect_tuple()
+        .with_context(|| format!("Line {line_number}: expected two letters, found {line:?}"))
 }
 
-fn compute_score(input_type: Input) -> Result<u32> {
-    let scores = include_str!("inputs/day2.txt")
+pub fn rounds(input: &str, rules: &Rules, input_type: &Input) -> Result<Vec<Round>> {
+    if let Input::Assignment(shapes) = input_type {
+        ensure!(
+            shapes.len() == rules.len(),
+            "Expected {} shapes in the assignment, found {}",
+            rules.len(),
+            shapes.len()
+        );
+    }
+
+    let play = |line: usize, left, right| -> Result<Round> {
+        let theirs = rules.opponent(left)?;
+        let mine = match input_type {
+            Input::Guide => rules.response(right)?,
+            Input::Plan => {
+                let wanted = rules.requirement(right)?;
+                let Some(mine) = rules.response_for(theirs, wanted) else {
+                    bail!("Nothing can {wanted:?} against {}", rules.name(theirs));
+                };
+                mine
+            }
+            Input::Assignment(shapes) => shapes[rules.response(right)?.0],
+        };
+
+        Ok(Round {
+            line,
+            theirs,
+            mine,
+            outcome: rules.outcome(mine, theirs),
+            score: rules.score(mine, theirs),
+        })
+    };
+
+    input
         .lines()
-        .map(|line| -> Result<u32> {
-            let (left, right) = line.split(' ').collect_tuple().context("Failed to parse")?;
+        .enumerate()
+        .map(|(i, line)| {
+            let (left, right) = parse_line(i + 1, line)?;
+            play(i + 1, left, right).with_context(|| format!("Line {}", i + 1))
+        })
+        .collect()
+}
+
+pub fn compute_score(input: &str, rules: &Rules, input_type: &Input) -> Result<u32> {
+    Ok(rounds(input, rules, input_type)?
+        .iter()
+        .map(|round| round.score)
+        .sum())
+}
+
+/// The shapes to read the response letters as for the highest total score, trying every
+/// permutation. The guide's own assignment wins any tie it is part of.
+pub fn best_assignment(input: &str, rules: &Rules) -> Result<(Vec<Shape>, u32)> {
+    // How often each (opponent, response letter) pair comes up, so each assignment costs
+    // n² rather than a pass over the whole guide
+    let mut counts = vec![vec![0; rules.len()]; rules.len()];
+    for (i, line) in input.lines().enumerate() {
+        let (left, right) = parse_line(i + 1, line)?;
+        let context = || format!("Line {}", i + 1);
+        let theirs = rules.opponent(left).with_context(context)?;
+        let letter = rules.response(right).with_context(context)?;
+        counts[theirs.0][letter.0] += 1;
+    }
+
+    rules
+        .shapes()
+        .permutations(rules.len())
+        .map(|shapes| {
+            let score = rules
+                .shapes()
+                .cartesian_product(rules.shapes())
+                .map(|(theirs, letter)| {
+                    counts[theirs.0][letter.0] * rules.score(shapes[letter.0], theirs)
+                })
+                .sum();
+            (shapes, score)
+        })
+        .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        .context("There are no shapes")
+}
 
-            let mov = parse!(left)?;
-            let resp = match input_type {
-                Input::Guide => parse!(right)?,
-                Input::Plan => Move::from_requirement(&parse!(right)?, &mov),
-            };
+/// Every round under both readings of the guide, their totals, and the best letter assignment.
+pub fn report(input: &str, rules: &Rules) -> Result<String> {
+    let guide = rounds(input, rules, &Input::Guide)?;
+    let plan = rounds(input, rules, &Input::Plan)?;
 
-            Ok((resp as u32) + (resp.against(&mov) as u32))
-        });
+    let mut out = String::new();
+    writeln!(out, "{:>5}  {:<28}  {:<28}", "Line", "Guide", "Plan")?;
+    let show = |round: &Round| {
+        format!(
+            "{} vs {}: {:?} {}",
+            rules.name(round.mine),
+            rules.name(round.theirs),
+            round.outcome,
+            round.score
+        )
+    };
+    for (guide, plan) in guide.iter().zip(&plan) {
+        writeln!(
+            out,
+            "{:>5}  {:<28}  {:<28}",
+            guide.line,
+            show(guide),
+            show(plan)
+        )?;
+    }
+
+    writeln!(out)?;
+    for (name, rounds) in [("Guide", &guide), ("Plan", &plan)] {
+        let Tally {
+            wins,
+            draws,
+            losses,
+        } = Tally::of(rounds);
+        let total = rounds.iter().map(|round| round.score).sum::<u32>();
+        writeln!(
+            out,
+            "{name}: {total} points, {wins} won, {draws} drawn, {losses} lost"
+        )?;
+    }
+
+    let (shapes, score) = best_assignment(input, rules)?;
+    let assignment = rules
+        .shapes()
+        .map(|letter| {
+            format!(
+                "{} = {}",
+                rules.response_letter(letter),
+                rules.name(shapes[letter.0])
+            )
+        })
+        .join(", ");
+    writeln!(out, "Best assignment: {assignment}, for {score} points")?;
+
+    Ok(out)
+}
+
+pub fn solve_part1(input: &str) -> Result<u32> {
+    compute_score(input, &Rules::rock_paper_scissors(), &Input::Guide)
+}
 
-    process_results(scores, |iter| iter.sum())
+pub fn solve_part2(input: &str) -> Result<u32> {
+    compute_score(input, &Rules::rock_paper_scissors(), &Input::Plan)
 }
 
 pub fn part1() -> Result<u32> {
-    compute_score(Input::Guide)
+    solve_part1(INPUT)
 }
 
 pub fn part2() -> Result<u32> {
-    compute_score(Input::Plan)
+    solve_part2(INPUT)
 }
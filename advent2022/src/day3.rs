@@ -1,46 +1,102 @@
-use std::collections::HashSet;
-
+use anyhow::{bail, ensure, Context, Result};
 use itertools::Itertools;
-use substring::Substring;
 
-fn score(c: &char) -> u32 {
-    if c.is_uppercase() {
-        (*c as u32) - 38
-    } else {
-        (*c as u32) - 96
+pub const INPUT: &str = include_str!("inputs/day3.txt");
+
+fn priority(c: char) -> Result<u32> {
+    match c {
+        'a'..='z' => Ok(c as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Ok(c as u32 - 'A' as u32 + 27),
+        _ => bail!("{c:?} is not an item"),
+    }
+}
+
+fn item(priority: u32) -> char {
+    match priority {
+        1..=26 => char::from(b'a' + priority as u8 - 1),
+        _ => char::from(b'A' + priority as u8 - 27),
+    }
+}
+
+/// The items in a rucksack, bit `n` set for the item of priority `n`.
+fn items(s: &str) -> Result<u64> {
+    s.chars()
+        .try_fold(0, |items, c| Ok(items | 1 << priority(c)?))
+}
+
+// The priority of the one item every set has in common
+fn common_priority(sets: impl IntoIterator<Item = u64>) -> Result<u32> {
+    let common = sets
+        .into_iter()
+        .fold(u64::MAX, |common, items| common & items);
+    match common.count_ones() {
+        0 => bail!("No item in common"),
+        1 => Ok(common.trailing_zeros()),
+        _ => {
+            let items = (1..=52)
+                .filter(|&p| common & 1 << p != 0)
+                .map(item)
+                .collect::<String>();
+            bail!("More than one item in common: {items}")
+        }
     }
 }
 
-pub fn part1() -> u32 {
-    include_str!("inputs/day3.txt")
+// The priority of the item in both compartments
+fn misplaced_priority(line: &str) -> Result<u32> {
+    ensure!(line.len() % 2 == 0, "Odd number of items");
+    ensure!(line.is_ascii(), "{line:?} is not all items");
+
+    let (first, second) = line.split_at(line.len() / 2);
+    common_priority([items(first)?, items(second)?])
+}
+
+pub fn solve_part1(input: &str) -> Result<u32> {
+    input
         .lines()
-        .map(|line| {
-            let len = line.len();
-            let mid = len / 2;
-            let compartment1 = line.substring(0, mid).chars().collect::<HashSet<_>>();
-            let compartment2 = line.substring(mid, len).chars().collect::<HashSet<_>>();
-
-            let mut intersection = compartment1.intersection(&compartment2);
-            score(intersection.next().expect("Compartments have overlap"))
-        })
+        .enumerate()
+        .map(|(i, line)| misplaced_priority(line).with_context(|| format!("Line {}", i + 1)))
         .sum()
 }
 
-pub fn part2() -> u32 {
-    include_str!("inputs/day3.txt")
+/// The sum of the badge priorities, one badge per `n` consecutive rucksacks.
+pub fn group_priorities(input: &str, n: usize) -> Result<u32> {
+    ensure!(n > 0, "Groups must have at least one rucksack");
+
+    input
         .lines()
-        .chunks(3)
+        .enumerate()
+        .chunks(n)
         .into_iter()
-        .map(|lines| {
-            let (bag1, bag2, bag3) = lines
-                .map(|s| s.chars().collect::<HashSet<_>>())
-                .collect_tuple()
-                .expect("Expected 3 bags");
+        .map(|group| {
+            let group = group.collect::<Vec<_>>();
+            let first = group[0].0 + 1;
+            let context = || format!("Group starting at line {first}");
 
-            let bag_1_and_2 = bag1.intersection(&bag2).copied().collect::<HashSet<_>>();
-            let mut intersection = bag_1_and_2.intersection(&bag3);
+            ensure!(
+                group.len() == n,
+                "{}: only {} of {n} rucksacks",
+                context(),
+                group.len()
+            );
+            let bags = group
+                .iter()
+                .map(|&(i, line)| items(line).with_context(|| format!("Line {}", i + 1)))
+                .collect::<Result<Vec<_>>>()?;
 
-            score(intersection.next().expect("Compartments have overlap"))
+            common_priority(bags).with_context(context)
         })
         .sum()
 }
+
+pub fn solve_part2(input: &str) -> Result<u32> {
+    group_priorities(input, 3)
+}
+
+pub fn part1() -> Result<u32> {
+    solve_part1(INPUT)
+}
+
+pub fn part2() -> Result<u32> {
+    solve_part2(INPUT)
+}
@@ -1,15 +1,20 @@
-use std::ops::Range;
+use std::{cmp::Ordering, fmt::Write, ops::RangeInclusive};
 
+use anyhow::{ensure, Context, Result};
 use nom::{
     bytes::complete::tag,
     character::complete::{char, one_of},
-    combinator::{map, map_res, recognize},
+    combinator::{all_consuming, map, map_res, recognize},
     multi::{many0, many1},
     sequence::{separated_pair, terminated},
-    IResult,
+    Finish, IResult,
 };
 
-use crate::utils::RangeExt;
+use crate::utils::InclusiveRangeExt;
+
+pub const INPUT: &str = include_str!("inputs/day4.txt");
+
+type Sections = RangeInclusive<u32>;
 
 fn decimal(input: &str) -> IResult<&str, u32> {
     map_res(
@@ -18,32 +23,129 @@ fn decimal(input: &str) -> IResult<&str, u32> {
     )(input)
 }
 
-fn range(input: &str) -> IResult<&str, Range<u32>> {
+fn range(input: &str) -> IResult<&str, Sections> {
     map(
         separated_pair(decimal, tag("-"), decimal),
-        |(lower, upper): (u32, u32)| lower..upper,
+        |(lower, upper): (u32, u32)| lower..=upper,
     )(input)
 }
 
-fn parse_line(input: &str) -> (Range<u32>, Range<u32>) {
-    let (_, (r1, r2)) = separated_pair(range, tag(","), range)(input).expect("Failed to parse");
-    (r1, r2)
+fn parse_line(input: &str) -> Result<(Sections, Sections)> {
+    let (_, (r1, r2)) = all_consuming(separated_pair(range, tag(","), range))(input)
+        .map_err(|e| e.to_owned())
+        .finish()?;
+    for r in [&r1, &r2] {
+        ensure!(!r.is_empty(), "{}-{} runs backwards", r.start(), r.end());
+    }
+
+    Ok((r1, r2))
 }
 
-pub fn count_ranges(predicate: fn(r1: Range<u32>, r2: Range<u32>) -> bool) -> usize {
-    include_str!("inputs/day4.txt")
+pub fn parse_input(input: &str) -> Result<Vec<(Sections, Sections)>> {
+    input
         .lines()
-        .filter(|line| {
-            let (r1, r2) = parse_line(line);
-            predicate(r1, r2)
-        })
-        .count()
+        .enumerate()
+        .map(|(i, line)| parse_line(line).with_context(|| format!("Line {}", i + 1)))
+        .collect()
+}
+
+pub fn count_ranges(input: &str, predicate: fn(&Sections, &Sections) -> bool) -> Result<usize> {
+    Ok(parse_input(input)?
+        .iter()
+        .filter(|(r1, r2)| predicate(r1, r2))
+        .count())
+}
+
+/// The most elves any one section is assigned to, and the runs of sections assigned that many.
+pub fn busiest_sections(pairs: &[(Sections, Sections)]) -> (usize, Vec<Sections>) {
+    // +1 where an assignment starts and -1 just past where it ends, in section order
+    let mut events = pairs
+        .iter()
+        .flat_map(|(r1, r2)| [r1, r2])
+        .flat_map(|r| [(*r.start() as u64, 1), (*r.end() as u64 + 1, -1)])
+        .collect::<Vec<(u64, isize)>>();
+    events.sort_unstable();
+
+    let (mut most, mut runs) = (0, Vec::new());
+    let mut elves = 0;
+    for (i, &(section, change)) in events.iter().enumerate() {
+        elves += change;
+        // Only look at a section once every change to it is counted
+        let Some(&(next, _)) = events.get(i + 1) else {
+            break;
+        };
+        if next == section {
+            continue;
+        }
+
+        let run = section as u32..=(next - 1) as u32;
+        match (elves as usize).cmp(&most) {
+            Ordering::Greater => (most, runs) = (elves as usize, vec![run]),
+            // One elf's assignment ending right where another's starts doesn't split the run
+            Ordering::Equal if most > 0 => match runs.last_mut() {
+                Some(last) if last.precedes(&run) => *last = last.extend_by(&run),
+                _ => runs.push(run),
+            },
+            _ => {}
+        }
+    }
+
+    (most, runs)
+}
+
+/// Pairs whose assignments overlap or touch, with the single assignment that could replace them.
+pub fn mergeable(pairs: &[(Sections, Sections)]) -> Vec<(usize, Sections)> {
+    pairs
+        .iter()
+        .enumerate()
+        .filter(|(_, (r1, r2))| r1.overlaps(r2) || r1.precedes(r2) || r2.precedes(r1))
+        .map(|(i, (r1, r2))| (i + 1, r1.extend_by(r2)))
+        .collect()
+}
+
+pub fn report(input: &str) -> Result<String> {
+    let pairs = parse_input(input)?;
+    let mut out = String::new();
+
+    let (most, runs) = busiest_sections(&pairs);
+    writeln!(out, "Busiest sections, assigned to {most} elves:")?;
+    for run in runs {
+        writeln!(out, "  {}-{}", run.start(), run.end())?;
+    }
+
+    let merges = mergeable(&pairs);
+    writeln!(out, "\n{} pairs could share one assignment:", merges.len())?;
+    for (line, merged) in merges {
+        let (r1, r2) = &pairs[line - 1];
+        writeln!(
+            out,
+            "{line:>5}  {}-{},{}-{} => {}-{}",
+            r1.start(),
+            r1.end(),
+            r2.start(),
+            r2.end(),
+            merged.start(),
+            merged.end()
+        )?;
+    }
+
+    Ok(out)
+}
+
+pub fn solve_part1(input: &str) -> Result<usize> {
+    count_ranges(input, |r1, r2| {
+        r1.contains_range(r2) || r2.contains_range(r1)
+    })
+}
+
+pub fn solve_part2(input: &str) -> Result<usize> {
+    count_ranges(input, |r1, r2| r1.overlaps(r2))
 }
 
-pub fn part1() -> usize {
-    count_ranges(|r1, r2| r1.contains_range(&r2) || r2.contains_range(&r1))
+pub fn part1() -> Result<usize> {
+    solve_part1(INPUT)
 }
 
-pub fn part2() -> usize {
-    count_ranges(|r1, r2| r1.overlaps(&r2))
+pub fn part2() -> Result<usize> {
+    solve_part2(INPUT)
 }
@@ -1,23 +1,28 @@
 use std::{
-    fmt::{self, Debug, Formatter},
-    ops::Sub,
+    fmt::{self, Debug, Display, Formatter},
+    io::{self, BufRead, Write},
+    ops::Range,
     str::FromStr,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use derive_more::{Deref, DerefMut};
+use itertools::Itertools;
 use nom::{
-    branch::alt,
-    bytes::complete::{tag, take},
-    character::complete::digit1,
-    combinator::{all_consuming, map, map_opt, map_res},
-    multi::separated_list1,
-    sequence::{delimited, preceded, tuple},
-    Finish, IResult,
+    bytes::complete::{tag, take_till1},
+    character::complete::{digit1, space0, space1},
+    combinator::{all_consuming, map, map_opt, map_res, recognize},
+    multi::{many0, separated_list1},
+    sequence::{delimited, preceded, terminated, tuple},
+    Finish, IResult, Offset,
 };
 
-#[derive(Copy, Clone, Deref)]
-struct Crate(char);
+use crate::utils::records;
+
+pub const INPUT: &str = include_str!("inputs/day5.txt");
+
+#[derive(Clone, PartialEq, Eq, Deref)]
+pub struct Crate(pub String);
 
 impl Debug for Crate {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -25,69 +30,164 @@ impl Debug for Crate {
     }
 }
 
-#[derive(Deref, DerefMut)]
-struct Stacks(Vec<Vec<Crate>>);
+#[derive(Clone, PartialEq, Eq, Deref, DerefMut)]
+pub struct Stacks(pub Vec<Vec<Crate>>);
 
 impl Debug for Stacks {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for (i, stack) in self.iter().enumerate() {
-            writeln!(f, "Stack {}: {:?}", i, stack)?;
+            writeln!(f, "Stack {i}: {stack:?}")?;
         }
         Ok(())
     }
 }
 
+/// Draws the stacks the way the puzzle does, tallest stack first, with the numbered footer.
+///
+/// Columns are widened to fit the longest label or stack number, and each crate and number is
+/// centered in its column.
+impl Display for Stacks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let label_width = self.iter().flatten().map(|c| c.chars().count());
+        let number_width = self.len().to_string().len();
+        let width = (label_width.max().unwrap_or(1) + 2).max(number_width);
+
+        let height = self.iter().map(Vec::len).max().unwrap_or_default();
+        for level in (0..height).rev() {
+            let layer = self.iter().map(|stack| match stack.get(level) {
+                Some(Crate(c)) => format!("{:^width$}", format!("[{c}]")),
+                None => " ".repeat(width),
+            });
+            writeln!(f, "{}", layer.format(" "))?;
+        }
+
+        let footer = (1..=self.len()).map(|number| format!("{number:^width$}"));
+        write!(f, "{}", footer.format(" "))
+    }
+}
+
 impl Stacks {
-    fn apply(&mut self, mov: Move) {
+    /// Lifts the top `amount` crates off of a stack, keeping their order.
+    pub fn lift(&mut self, from: usize, amount: usize) -> Vec<Crate> {
+        let from = &mut self[from];
+        from.drain((from.len() - amount)..).collect()
+    }
+
+    /// The label on top of each stack, failing if any stack is empty.
+    pub fn tops(&self) -> Result<String> {
+        self.iter()
+            .enumerate()
+            .map(|(i, stack)| {
+                stack
+                    .last()
+                    .map(|c| c.as_str())
+                    .with_context(|| format!("Stack {} is empty", i + 1))
+            })
+            .collect()
+    }
+
+    fn check(&self, mov: Move) -> Result<()> {
+        for stack in [mov.from, mov.to] {
+            ensure!(stack < self.len(), "Stack {} does not exist", stack + 1);
+        }
+
+        let height = self[mov.from].len();
+        ensure!(
+            mov.amount as usize <= height,
+            "Stack {} only holds {height} crates",
+            mov.from + 1
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub amount: u32,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Move { amount, from, to } = self;
+        write!(f, "move {amount} from {} to {}", from + 1, to + 1)
+    }
+}
+
+/// The rules a crane follows when carrying out a [`Move`].
+pub trait CraneModel {
+    fn apply(&self, stacks: &mut Stacks, mov: Move);
+}
+
+/// Moves one crate at a time, reversing the order of the moved crates.
+pub struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn apply(&self, stacks: &mut Stacks, mov: Move) {
         (0..mov.amount).for_each(|_| {
-            let thing = self[mov.from].pop().unwrap();
-            self[mov.to].push(thing);
+            let thing = stacks[mov.from].pop().unwrap();
+            stacks[mov.to].push(thing);
         })
     }
+}
+
+/// Moves all the crates at once, keeping their order.
+pub struct CrateMover9001;
 
-    fn apply_bulk(&mut self, mov: Move) {
-        let from = &mut self[mov.from];
-        let stack = from
-            .drain((from.len() - mov.amount as usize)..)
-            .collect::<Vec<_>>();
-        self[mov.to].extend(stack)
+impl CraneModel for CrateMover9001 {
+    fn apply(&self, stacks: &mut Stacks, mov: Move) {
+        let lifted = stacks.lift(mov.from, mov.amount as usize);
+        stacks[mov.to].extend(lifted)
     }
 }
 
-#[derive(Debug)]
-struct Move {
-    amount: u32,
-    from: usize,
-    to: usize,
+/// Moves at most `capacity` crates per lift, keeping the order within each lift.
+pub struct CapacityLimited {
+    pub capacity: u32,
 }
 
-fn parse_crate(i: &str) -> IResult<&str, Crate> {
-    let one_crate = delimited(tag("["), take(1_usize), tag("]"));
-    map_opt(one_crate, |s: &str| s.chars().next().map(Crate))(i)
+impl CraneModel for CapacityLimited {
+    fn apply(&self, stacks: &mut Stacks, mov: Move) {
+        let mut remaining = mov.amount;
+        while remaining > 0 {
+            let amount = remaining.min(self.capacity.max(1));
+            CrateMover9001.apply(stacks, Move { amount, ..mov });
+            remaining -= amount;
+        }
+    }
 }
 
-fn parse_hole(i: &str) -> IResult<&str, ()> {
-    map(tag("   "), drop)(i)
+pub fn simulate(mut stacks: Stacks, moves: &[Move], crane: &impl CraneModel) -> Result<Stacks> {
+    for (i, &mov) in moves.iter().enumerate() {
+        stacks
+            .check(mov)
+            .with_context(|| format!("Move {} ({mov}) is invalid", i + 1))?;
+        crane.apply(&mut stacks, mov);
+    }
+    Ok(stacks)
 }
 
-fn parse_slot(i: &str) -> IResult<&str, Option<Crate>> {
-    alt((map(parse_crate, Some), map(parse_hole, |_| None)))(i)
+fn parse_crate(i: &str) -> IResult<&str, &str> {
+    let label = take_till1(|c: char| c == ']' || c.is_whitespace());
+    recognize(delimited(tag("["), label, tag("]")))(i)
 }
 
-fn parse_layer(i: &str) -> IResult<&str, Vec<Option<Crate>>> {
-    let pad = |mut layer: Vec<Option<Crate>>| {
-        layer.resize(9, None);
-        layer
-    };
-    map(separated_list1(tag(" "), parse_slot), pad)(i)
+fn parse_layer(i: &str) -> IResult<&str, Vec<&str>> {
+    terminated(many0(preceded(space0, parse_crate)), space0)(i)
+}
+
+fn parse_footer(i: &str) -> IResult<&str, Vec<&str>> {
+    delimited(space0, separated_list1(space1, digit1), space0)(i)
 }
 
 fn parse_number<N: FromStr>(i: &str) -> IResult<&str, N> {
     map_res(digit1, str::parse)(i)
 }
 
-fn parse_stack_number<T: FromStr + Sub<usize, Output = T>>(i: &str) -> IResult<&str, T> {
-    map(parse_number::<T>, |i| i - 1usize)(i)
+fn parse_stack_number(i: &str) -> IResult<&str, usize> {
+    map_opt(parse_number::<usize>, |n| n.checked_sub(1))(i)
 }
 
 fn parse_move(i: &str) -> IResult<&str, Move> {
@@ -101,73 +201,214 @@ fn parse_move(i: &str) -> IResult<&str, Move> {
     )(i)
 }
 
-fn parse_input() -> (Stacks, Vec<Move>) {
-    let lines = &mut include_str!("inputs/day5.txt").lines();
+fn span(line: &str, fragment: &str) -> Range<usize> {
+    let start = line.offset(fragment);
+    start..start + fragment.len()
+}
 
-    let layers = lines
-        .map_while(|line| {
-            all_consuming(parse_layer)(line)
-                .finish()
-                .ok()
-                .map(|(_, layer)| layer)
-        })
-        .collect();
+/// Parses the drawing of the stacks, using the numbered footer to decide how many stacks there
+/// are and which column each crate sits in.
+fn parse_drawing(lines: &[(usize, &str)]) -> Result<Stacks> {
+    let Some((&(footer_number, footer), layers)) = lines.split_last() else {
+        bail!("Missing drawing of the stacks");
+    };
 
-    let stacks = transpose_reverse(layers);
+    let (_, numbers) = all_consuming(parse_footer)(footer)
+        .finish()
+        .map_err(|_| anyhow!("Line {footer_number}: expected stack numbers"))?;
+    let mut columns = vec![];
+    for (i, number) in numbers.into_iter().enumerate() {
+        ensure!(
+            number.parse() == Ok(i + 1),
+            "Line {footer_number}: expected stack {} but found {number}",
+            i + 1
+        );
+        columns.push(span(footer, number));
+    }
+
+    let mut stacks = vec![vec![]; columns.len()];
+    for (height, &(line_number, layer)) in layers.iter().rev().enumerate() {
+        let (_, crates) = all_consuming(parse_layer)(layer)
+            .finish()
+            .map_err(|_| anyhow!("Line {line_number}: expected a layer of crates"))?;
+
+        for label in crates {
+            let crate_span = span(layer, label);
+            let stack = columns
+                .iter()
+                .position(|column| column.start < crate_span.end && crate_span.start < column.end)
+                .with_context(|| format!("Line {line_number}: {label} is not above any stack"))?;
+            ensure!(
+                stacks[stack].len() == height,
+                "Line {line_number}: {label} is not resting on top of stack {}",
+                stack + 1
+            );
+            stacks[stack].push(Crate(label[1..label.len() - 1].to_string()));
+        }
+    }
+
+    Ok(Stacks(stacks))
+}
+
+pub fn parse_input(input: &str) -> Result<(Stacks, Vec<Move>)> {
+    let mut lines = records(input).map(|(line_number, record)| {
+        record
+            .lines()
+            .enumerate()
+            .map(move |(i, line)| (line_number + i, line))
+    });
+
+    let drawing = lines.next().into_iter().flatten().collect::<Vec<_>>();
+    let stacks = parse_drawing(&drawing)?;
 
     let moves = lines
-        .filter_map(|line| {
-            all_consuming(parse_move)(line)
+        .flatten()
+        .map(|(line_number, line)| {
+            all_consuming(parse_move)(line.trim_end())
                 .finish()
                 .map(|(_, mov)| mov)
-                .ok()
+                .map_err(|_| anyhow!("Line {line_number}: invalid move {line:?}"))
         })
-        .collect();
-
-    (Stacks(stacks), moves)
-}
-
-fn transpose_reverse<T: Debug>(v: Vec<Vec<Option<T>>>) -> Vec<Vec<T>> {
-    assert!(!v.is_empty());
-    let len = v[0].len();
-    let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| {
-            iters
-                .iter_mut()
-                .rev()
-                .filter_map(|n| n.next().unwrap())
-                .collect::<Vec<T>>()
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((stacks, moves))
+}
+
+/// Writes out a puzzle in the same format [`parse_input`] reads.
+pub fn serialize(stacks: &Stacks, moves: &[Move]) -> String {
+    let moves = moves.iter().format("\n");
+    format!("{stacks}\n\n{moves}\n")
+}
+
+pub fn solve_part1(input: &str) -> Result<String> {
+    let (stacks, moves) = parse_input(input)?;
+    simulate(stacks, &moves, &CrateMover9000)?.tops()
+}
+
+pub fn solve_part2(input: &str) -> Result<String> {
+    let (stacks, moves) = parse_input(input)?;
+    simulate(stacks, &moves, &CrateMover9001)?.tops()
+}
+
+/// Steps through the rearrangement procedure one move at a time, in either direction.
+pub struct Replay<C> {
+    initial: Stacks,
+    moves: Vec<Move>,
+    crane: C,
+    stacks: Stacks,
+    position: usize,
+}
+
+impl<C: CraneModel> Replay<C> {
+    pub fn new(input: &str, crane: C) -> Result<Self> {
+        let (stacks, moves) = parse_input(input)?;
+        // Validate up front so stepping through can't fail
+        simulate(stacks.clone(), &moves, &crane)?;
+
+        Ok(Self {
+            initial: stacks.clone(),
+            moves,
+            crane,
+            stacks,
+            position: 0,
         })
-        .collect()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn tops(&self) -> Result<String> {
+        self.stacks.tops()
+    }
+
+    pub fn forward(&mut self) -> bool {
+        let Some(&mov) = self.moves.get(self.position) else {
+            return false;
+        };
+
+        self.crane.apply(&mut self.stacks, mov);
+        self.position += 1;
+        true
+    }
+
+    pub fn back(&mut self) -> bool {
+        match self.position.checked_sub(1) {
+            Some(position) => {
+                self.jump_to(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves to the state after the first `n` moves have been applied.
+    pub fn jump_to(&mut self, n: usize) {
+        let n = n.min(self.moves.len());
+        if n < self.position {
+            self.stacks = self.initial.clone();
+            self.position = 0;
+        }
+        while self.position < n {
+            self.forward();
+        }
+    }
+
+    /// Drives the replay from line based commands: an empty line or `n` steps forward,
+    /// `p` steps back, a number jumps to that move, and `q` quits.
+    pub fn run_interactive(
+        &mut self,
+        input: impl BufRead,
+        mut output: impl Write,
+    ) -> io::Result<()> {
+        writeln!(output, "{self}")?;
+        for line in input.lines() {
+            match line?.trim() {
+                "" | "n" => {
+                    self.forward();
+                }
+                "p" => {
+                    self.back();
+                }
+                "q" => break,
+                n => match n.parse() {
+                    Ok(n) => self.jump_to(n),
+                    Err(_) => {
+                        writeln!(output, "Unknown command {n:?}")?;
+                        continue;
+                    }
+                },
+            }
+            writeln!(output, "{self}")?;
+        }
+
+        Ok(())
+    }
 }
 
-pub fn part1() -> Result<String> {
-    let (mut stacks, moves) = parse_input();
-    for mov in moves {
-        println!("{stacks:?}");
-        println!("{mov:?}");
-        stacks.apply(mov);
+impl<C: CraneModel> Display for Replay<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.position.checked_sub(1).map(|i| self.moves[i]) {
+            Some(mov) => writeln!(f, "After move {}/{}: {mov}", self.position, self.len())?,
+            None => writeln!(f, "Initial state, {} moves", self.len())?,
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", self.stacks)
     }
-    println!("{stacks:?}");
+}
 
-    Ok(stacks
-        .iter()
-        .map(|stack| **stack.last().expect("Empty stack!"))
-        .collect::<String>())
+pub fn part1() -> Result<String> {
+    solve_part1(INPUT)
 }
 
 pub fn part2() -> Result<String> {
-    let (mut stacks, moves) = parse_input();
-    for mov in moves {
-        println!("{stacks:?}");
-        println!("{mov:?}");
-        stacks.apply_bulk(mov);
-    }
-    println!("{stacks:?}");
-
-    Ok(stacks
-        .iter()
-        .map(|stack| **stack.last().expect("Empty stack!"))
-        .collect::<String>())
+    solve_part2(INPUT)
 }
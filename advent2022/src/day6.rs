@@ -1,16 +1,92 @@
-use itertools::Itertools;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    io::{self, BufReader, Read},
+};
 
-fn find_n_unique(n: usize) -> Option<usize> {
-    include_str!("inputs/day6.txt")
-        .as_bytes()
-        .windows(n)
-        .position(|window| window.iter().unique().count() == n)
-        .map(|i| i + n)
+pub const INPUT: &str = include_str!("inputs/day6.txt");
+
+// Where each symbol was last seen, counting from 1 so 0 is never
+trait LastSeen<T> {
+    fn replace(&mut self, symbol: T, position: usize) -> usize;
+}
+
+impl LastSeen<u8> for [usize; 256] {
+    fn replace(&mut self, byte: u8, position: usize) -> usize {
+        std::mem::replace(&mut self[byte as usize], position)
+    }
+}
+
+impl<T: Eq + Hash> LastSeen<T> for HashMap<T, usize> {
+    fn replace(&mut self, symbol: T, position: usize) -> usize {
+        self.insert(symbol, position).unwrap_or(0)
+    }
+}
+
+/// Whether the last `n` symbols seen were all different, one symbol at a time. No run of zero
+/// symbols counts, so `n == 0` never matches.
+struct Window<S> {
+    n: usize,
+    last_seen: S,
+    // Where the longest run of different symbols ending at the current one starts
+    start: usize,
+    position: usize,
+}
+
+impl<S> Window<S> {
+    fn new(n: usize, last_seen: S) -> Self {
+        Self {
+            n,
+            last_seen,
+            start: 1,
+            position: 0,
+        }
+    }
+
+    fn push<T>(&mut self, symbol: T) -> bool
+    where
+        S: LastSeen<T>,
+    {
+        self.position += 1;
+        let last = self.last_seen.replace(symbol, self.position);
+        self.start = self.start.max(last + 1);
+
+        self.n > 0 && self.position + 1 - self.start >= self.n
+    }
+}
+
+/// How many symbols in the first `n` all different ones end, from any alphabet.
+pub fn find_n_unique<T: Eq + Hash>(signal: impl IntoIterator<Item = T>, n: usize) -> Option<usize> {
+    let mut window = Window::new(n, HashMap::new());
+    signal
+        .into_iter()
+        .position(|symbol| window.push(symbol))
+        .map(|i| i + 1)
+}
+
+/// Every position a run of `n` different bytes ends at, read from a signal of any length.
+pub fn markers(reader: impl Read, n: usize) -> impl Iterator<Item = io::Result<usize>> {
+    let mut window = Window::new(n, [0; 256]);
+    BufReader::new(reader)
+        .bytes()
+        .filter_map(move |byte| match byte {
+            Ok(byte) => window.push(byte).then_some(Ok(window.position)),
+            Err(e) => Some(Err(e)),
+        })
+}
+
+pub fn solve_part1(input: &str) -> Option<usize> {
+    find_n_unique(input.chars(), 4)
+}
+
+pub fn solve_part2(input: &str) -> Option<usize> {
+    find_n_unique(input.chars(), 14)
 }
 
 pub fn part1() -> Option<usize> {
-    find_n_unique(4)
+    solve_part1(INPUT)
 }
+
 pub fn part2() -> Option<usize> {
-    find_n_unique(14)
-}
\ No newline at end of file
+    solve_part2(INPUT)
+}
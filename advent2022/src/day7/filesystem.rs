@@ -0,0 +1,439 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter, Write as _},
+    io::{self, BufRead, Write},
+};
+
+use anyhow::{bail, Context, Result};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use id_tree::{InsertBehavior, Node, NodeId, Tree};
+
+use super::parsing::{parse_command, parse_input, Command, FileEntry, Listing, SizeFilter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Directory,
+    File { size: usize },
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Directory => write!(f, "a directory"),
+            Kind::File { size } => write!(f, "a file of size {size}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    kind: Kind,
+}
+
+/// A byte count, printed like `du -h` with the alternate flag (`{:#}`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Size(pub usize);
+
+impl Display for Size {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut size = self.0 as f64;
+        if !f.alternate() || size < 1024.0 {
+            return write!(f, "{}", self.0);
+        }
+
+        for unit in ["K", "M", "G", "T"] {
+            size /= 1024.0;
+            if size < 1024.0 || unit == "T" {
+                return match size < 10.0 {
+                    true => write!(f, "{size:.1}{unit}"),
+                    false => write!(f, "{size:.0}{unit}"),
+                };
+            }
+        }
+        unreachable!()
+    }
+}
+
+fn show(size: usize, human: bool) -> String {
+    match human {
+        true => format!("{:#}", Size(size)),
+        false => size.to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Directory {
+    pub id: NodeId,
+    pub size: usize,
+    pub below: usize,
+}
+
+/// In-memory filesystem rebuilt from a terminal transcript.
+#[derive(Debug)]
+pub struct FileSystem {
+    tree: Tree<Entry>,
+    root: NodeId,
+    cwd: NodeId,
+    // Each name appears once per directory, so this identifies entries however they were reached
+    index: HashMap<(NodeId, String), NodeId>,
+    // Total size of every node, refreshed once the transcript is replayed
+    sizes: HashMap<NodeId, usize>,
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        let mut tree = Tree::new();
+        let root = tree
+            .insert(
+                Node::new(Entry {
+                    name: "/".into(),
+                    kind: Kind::Directory,
+                }),
+                InsertBehavior::AsRoot,
+            )
+            .expect("Empty tree has room for a root");
+
+        Self {
+            tree,
+            cwd: root.clone(),
+            index: HashMap::new(),
+            sizes: HashMap::from([(root.clone(), 0)]),
+            root,
+        }
+    }
+
+    /// Replays every command of a transcript, leaving the working directory at `/`.
+    pub fn from_transcript(input: &str) -> Result<Self> {
+        let mut fs = Self::new();
+        for (i, command) in parse_input(input)?.into_iter().enumerate() {
+            fs.replay(command)
+                .with_context(|| format!("Failed to replay command {}", i + 1))?;
+        }
+        fs.cwd = fs.root.clone();
+        fs.update_sizes()?;
+
+        Ok(fs)
+    }
+
+    fn replay(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Cd { directory } => self.cwd = self.resolve_or_create(&directory)?,
+            Command::Ls { listings } => {
+                for listing in listings {
+                    let (name, kind) = match listing {
+                        Listing::Directory(dir) => (dir.name, Kind::Directory),
+                        Listing::File(FileEntry { size, path }) => (path, Kind::File { size }),
+                    };
+                    self.insert(&self.cwd.clone(), name.as_str(), kind)?;
+                }
+            }
+            Command::Pwd | Command::Du { .. } | Command::Find { .. } | Command::Tree { .. } => (),
+        }
+
+        Ok(())
+    }
+
+    fn entry(&self, id: &NodeId) -> &Entry {
+        self.tree
+            .get(id)
+            .expect("Ids never outlive their node")
+            .data()
+    }
+
+    fn child(&self, parent: &NodeId, name: &str) -> Option<NodeId> {
+        self.index.get(&(parent.clone(), name.to_string())).cloned()
+    }
+
+    /// Adds `name` under `parent`, or returns the existing entry if it was seen before.
+    fn insert(&mut self, parent: &NodeId, name: &str, kind: Kind) -> Result<NodeId> {
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            bail!("Invalid name {name:?}");
+        }
+
+        if let Some(existing) = self.child(parent, name) {
+            let existing_kind = self.entry(&existing).kind;
+            if existing_kind != kind {
+                let path = self.path_of(&existing);
+                bail!("{path} is {existing_kind} but was listed as {kind}");
+            }
+            return Ok(existing);
+        }
+
+        let entry = Entry {
+            name: name.into(),
+            kind,
+        };
+        let id = self
+            .tree
+            .insert(Node::new(entry), InsertBehavior::UnderNode(parent))?;
+        self.index.insert((parent.clone(), name.into()), id.clone());
+
+        Ok(id)
+    }
+
+    // One component of a path walk, `None` if the named entry doesn't exist
+    fn step(&self, curr: NodeId, component: Utf8Component) -> Result<Option<NodeId>> {
+        if self.entry(&curr).kind != Kind::Directory {
+            bail!("Not a directory");
+        }
+
+        Ok(match component {
+            Utf8Component::RootDir | Utf8Component::Prefix(_) => Some(self.root.clone()),
+            Utf8Component::CurDir => Some(curr),
+            // Like a real shell, `..` at the root stays put
+            Utf8Component::ParentDir => {
+                Some(self.tree.get(&curr)?.parent().cloned().unwrap_or(curr))
+            }
+            Utf8Component::Normal(name) => self.child(&curr, name),
+        })
+    }
+
+    fn resolve(&self, path: Option<&Utf8Path>) -> Result<NodeId> {
+        let Some(path) = path else {
+            return Ok(self.cwd.clone());
+        };
+
+        let mut curr = self.cwd.clone();
+        for component in path.components() {
+            curr = self
+                .step(curr, component)
+                .and_then(|id| id.context("No such file or directory"))
+                .with_context(|| path.to_owned())?;
+        }
+
+        Ok(curr)
+    }
+
+    // Walks `path` like `mkdir -p`, the transcript proves it exists even if it was never listed
+    fn resolve_or_create(&mut self, path: &Utf8Path) -> Result<NodeId> {
+        let mut curr = self.cwd.clone();
+        for component in path.components() {
+            curr = match self
+                .step(curr.clone(), component)
+                .with_context(|| path.to_owned())?
+            {
+                Some(id) => id,
+                None => self.insert(&curr, component.as_str(), Kind::Directory)?,
+            };
+        }
+        if self.entry(&curr).kind != Kind::Directory {
+            bail!("{path}: Not a directory");
+        }
+
+        Ok(curr)
+    }
+
+    // Built from the names up to the root, so only paths that are asked for cost anything
+    pub(super) fn path_of(&self, id: &NodeId) -> Utf8PathBuf {
+        let ancestors = self
+            .tree
+            .ancestor_ids(id)
+            .expect("Ids never outlive their node")
+            .collect::<Vec<_>>();
+
+        let mut path = Utf8PathBuf::from("/");
+        for id in ancestors.into_iter().rev().skip(1).chain([id]) {
+            if id != &self.root {
+                path.push(&self.entry(id).name);
+            }
+        }
+        path
+    }
+
+    fn update_sizes(&mut self) -> Result<()> {
+        self.sizes.clear();
+        for id in self.tree.traverse_post_order_ids(&self.root)? {
+            let size = match self.entry(&id).kind {
+                Kind::File { size } => size,
+                Kind::Directory => self.tree.children_ids(&id)?.map(|c| self.sizes[c]).sum(),
+            };
+            self.sizes.insert(id, size);
+        }
+
+        Ok(())
+    }
+
+    fn size(&self, id: &NodeId) -> usize {
+        self.sizes[id]
+    }
+
+    pub fn cd(&mut self, path: &Utf8Path) -> Result<()> {
+        let id = self.resolve(Some(path))?;
+        if self.entry(&id).kind != Kind::Directory {
+            bail!("{path}: Not a directory");
+        }
+        self.cwd = id;
+
+        Ok(())
+    }
+
+    pub fn pwd(&self) -> Utf8PathBuf {
+        self.path_of(&self.cwd)
+    }
+
+    /// Lines of `ls` output for the working directory, in the puzzle's format.
+    pub fn ls(&self) -> Vec<String> {
+        self.tree
+            .children(&self.cwd)
+            .expect("Ids never outlive their node")
+            .map(|child| match child.data() {
+                Entry {
+                    name,
+                    kind: Kind::Directory,
+                    ..
+                } => format!("dir {name}"),
+                Entry {
+                    name,
+                    kind: Kind::File { size },
+                    ..
+                } => format!("{size} {name}"),
+            })
+            .collect()
+    }
+
+    // Every directory with its total size, children before parents and the root last. A
+    // directory's subdirectories are the `below` entries right before it.
+    pub(super) fn directories(&self) -> Result<Vec<Directory>> {
+        let mut directories: Vec<Directory> = vec![];
+        for id in self.tree.traverse_post_order_ids(&self.root)? {
+            if self.entry(&id).kind != Kind::Directory {
+                continue;
+            }
+
+            // Walk back over the children, each of which spans itself and its own `below`
+            let (mut below, mut i) = (0, directories.len());
+            while i > 0 && self.tree.get(&directories[i - 1].id)?.parent() == Some(&id) {
+                let child = &directories[i - 1];
+                below += child.below + 1;
+                i -= child.below + 1;
+            }
+
+            let size = self.size(&id);
+            directories.push(Directory { id, size, below });
+        }
+
+        Ok(directories)
+    }
+
+    /// Every directory under `path` with its total size, children before parents.
+    pub fn du(&self, path: Option<&Utf8Path>) -> Result<Vec<(Utf8PathBuf, usize)>> {
+        let start = self.resolve(path)?;
+
+        Ok(self
+            .tree
+            .traverse_post_order_ids(&start)?
+            .filter(|id| self.entry(id).kind == Kind::Directory)
+            .map(|id| (self.path_of(&id), self.size(&id)))
+            .collect())
+    }
+
+    /// The `n` largest directories, biggest first and ties in `du` order.
+    pub fn largest(&self, n: usize) -> Result<Vec<(Utf8PathBuf, usize)>> {
+        let mut directories = self.directories()?;
+        directories.sort_by(|a, b| b.size.cmp(&a.size));
+        directories.truncate(n);
+
+        Ok(directories
+            .into_iter()
+            .map(|Directory { id, size, .. }| (self.path_of(&id), size))
+            .collect())
+    }
+
+    /// Every entry under `path`, or only the files matching `filter` if one is given.
+    pub fn find(
+        &self,
+        path: Option<&Utf8Path>,
+        filter: Option<SizeFilter>,
+    ) -> Result<Vec<Utf8PathBuf>> {
+        let start = self.resolve(path)?;
+
+        Ok(self
+            .tree
+            .traverse_pre_order_ids(&start)?
+            .filter(|id| match (self.entry(id).kind, filter) {
+                (_, None) => true,
+                (Kind::File { size }, Some(filter)) => filter.matches(size),
+                (Kind::Directory, Some(_)) => false,
+            })
+            .map(|id| self.path_of(&id))
+            .collect())
+    }
+
+    /// Indented listing in the style of the puzzle description, with directory sizes included.
+    pub fn tree(&self, path: Option<&Utf8Path>, human: bool) -> Result<String> {
+        let start = self.resolve(path)?;
+
+        // Pre-order, with each entry's depth below `start` carried along
+        let mut out = String::new();
+        let mut stack = vec![(start, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            let children = self.tree.children_ids(&id)?.cloned().collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+
+            let indent = 2 * depth;
+            let Entry { name, kind } = self.entry(&id);
+            let size = show(self.size(&id), human);
+            match kind {
+                Kind::Directory => writeln!(out, "{:indent$}- {name} (dir, size={size})", "")?,
+                Kind::File { .. } => writeln!(out, "{:indent$}- {name} (file, size={size})", "")?,
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Runs a single shell command, returning what it prints.
+    pub fn execute(&mut self, line: &str) -> Result<String> {
+        let lines = match parse_command(line)? {
+            Command::Cd { directory } => {
+                self.cd(&directory)?;
+                vec![]
+            }
+            Command::Ls { .. } => self.ls(),
+            Command::Pwd => vec![self.pwd().into_string()],
+            Command::Du { path, human } => self
+                .du(path.as_deref())?
+                .into_iter()
+                .map(|(path, size)| format!("{}\t{path}", show(size, human)))
+                .collect(),
+            Command::Find { path, size } => self
+                .find(path.as_deref(), size)?
+                .into_iter()
+                .map(Utf8PathBuf::into_string)
+                .collect(),
+            Command::Tree { path, human } => return self.tree(path.as_deref(), human),
+        };
+
+        Ok(lines.into_iter().map(|line| line + "\n").collect())
+    }
+
+    pub fn run_interactive(
+        &mut self,
+        input: impl BufRead,
+        mut output: impl Write,
+    ) -> io::Result<()> {
+        write!(output, "{} $ ", self.pwd())?;
+        output.flush()?;
+        for line in input.lines() {
+            match line?.trim() {
+                "" => (),
+                "exit" | "q" => break,
+                line => match self.execute(line) {
+                    Ok(printed) => write!(output, "{printed}")?,
+                    Err(e) => writeln!(output, "{e:#}")?,
+                },
+            }
+            write!(output, "{} $ ", self.pwd())?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
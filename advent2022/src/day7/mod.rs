@@ -1,5 +1,17 @@
+mod filesystem;
 mod parsing;
 mod solution;
 
+pub use filesystem::{FileSystem, Size};
+pub use parsing::SizeFilter;
+pub use solution::deletion_candidate;
+pub use solution::deletion_set;
 pub use solution::part1;
 pub use solution::part2;
+pub use solution::report;
+pub use solution::solve_part1;
+pub use solution::solve_part2;
+pub use solution::DiskError;
+pub use solution::DiskPolicy;
+
+pub const INPUT: &str = include_str!("../inputs/day7.txt");
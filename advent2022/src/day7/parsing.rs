@@ -2,26 +2,60 @@ use anyhow::Result;
 use camino::Utf8PathBuf;
 use nom::{
     branch::alt,
-    bytes::complete::tag,
+    bytes::complete::{tag, take_till1},
     character::complete::{
-        alphanumeric1, digit1, line_ending, multispace0, multispace1, not_line_ending,
+        digit1, line_ending, multispace0, not_line_ending, one_of, space0, space1, u64,
     },
-    combinator::{all_consuming, map, map_res},
-    multi::{many1, separated_list1},
-    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    combinator::{all_consuming, eof, map, map_opt, map_res, opt, verify},
+    multi::many0,
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     Finish, IResult,
 };
 
 #[derive(Debug)]
 pub(super) enum Command {
-    Cd { directory: Utf8PathBuf },
-    Ls { listings: Vec<Listing> },
+    Cd {
+        directory: Utf8PathBuf,
+    },
+    Ls {
+        listings: Vec<Listing>,
+    },
+    Pwd,
+    Du {
+        path: Option<Utf8PathBuf>,
+        human: bool,
+    },
+    Find {
+        path: Option<Utf8PathBuf>,
+        size: Option<SizeFilter>,
+    },
+    Tree {
+        path: Option<Utf8PathBuf>,
+        human: bool,
+    },
+}
+
+/// The `-size` predicate of `find`: `+N` is strictly larger, `-N` strictly smaller and `N` exact.
+/// `N` may carry a `k`, `M` or `G` suffix for powers of 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeFilter {
+    Less(usize),
+    Exactly(usize),
+    Greater(usize),
+}
+
+impl SizeFilter {
+    pub fn matches(self, size: usize) -> bool {
+        match self {
+            SizeFilter::Less(n) => size < n,
+            SizeFilter::Exactly(n) => size == n,
+            SizeFilter::Greater(n) => size > n,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(super) struct DirectoryEntry {
-    // Nice for showing solution
-    #[allow(unused)]
     pub name: Utf8PathBuf,
 }
 
@@ -38,36 +72,102 @@ pub(super) enum Listing {
 }
 
 fn prompt(i: &str) -> IResult<&str, &str> {
-    terminated(tag("$"), multispace1)(i)
+    terminated(tag("$"), space1)(i)
+}
+
+fn path(i: &str) -> IResult<&str, Utf8PathBuf> {
+    map(take_till1(char::is_whitespace), Utf8PathBuf::from)(i)
+}
+
+// Anything up to the end of the line, so names may contain spaces
+fn name(i: &str) -> IResult<&str, Utf8PathBuf> {
+    map(
+        verify(not_line_ending, |s: &str| !s.trim().is_empty()),
+        |s: &str| s.trim().into(),
+    )(i)
 }
 
 fn cd(i: &str) -> IResult<&str, Command> {
-    let (i, _) = preceded(tag("cd"), multispace1)(i)?;
-    let (i, directory) = alt((alphanumeric1, tag(".."), tag("/")))(i)?;
+    let (i, directory) = preceded(pair(tag("cd"), space1), name)(i)?;
 
-    Ok((
-        i,
-        Command::Cd {
-            directory: directory.into(),
-        },
-    ))
+    Ok((i, Command::Cd { directory }))
 }
 
-fn directory(i: &str) -> IResult<&str, DirectoryEntry> {
+fn ls(i: &str) -> IResult<&str, Command> {
+    map(tag("ls"), |_| Command::Ls { listings: vec![] })(i)
+}
+
+fn pwd(i: &str) -> IResult<&str, Command> {
+    map(tag("pwd"), |_| Command::Pwd)(i)
+}
+
+// `-h`, print sizes in human readable units
+fn human(i: &str) -> IResult<&str, bool> {
+    map(opt(pair(space1, tag("-h"))), |flag| flag.is_some())(i)
+}
+
+fn du(i: &str) -> IResult<&str, Command> {
+    map(
+        preceded(tag("du"), pair(human, opt(preceded(space1, path)))),
+        |(human, path)| Command::Du { path, human },
+    )(i)
+}
+
+fn tree(i: &str) -> IResult<&str, Command> {
     map(
-        preceded(tuple((tag("dir"), multispace1)), alphanumeric1),
-        |s: &str| DirectoryEntry { name: s.into() },
+        preceded(tag("tree"), pair(human, opt(preceded(space1, path)))),
+        |(human, path)| Command::Tree { path, human },
     )(i)
 }
 
+fn size_filter(i: &str) -> IResult<&str, SizeFilter> {
+    let (i, (sign, n)) = preceded(
+        pair(tag("-size"), space1),
+        pair(
+            opt(one_of("+-")),
+            map_opt(pair(u64, opt(one_of("kMG"))), |(n, unit)| {
+                let unit = match unit {
+                    Some('k') => 1 << 10,
+                    Some('M') => 1 << 20,
+                    Some('G') => 1 << 30,
+                    _ => 1,
+                };
+                usize::try_from(n).ok()?.checked_mul(unit)
+            }),
+        ),
+    )(i)?;
+
+    let filter = match sign {
+        Some('+') => SizeFilter::Greater(n),
+        Some('-') => SizeFilter::Less(n),
+        _ => SizeFilter::Exactly(n),
+    };
+
+    Ok((i, filter))
+}
+
+fn find(i: &str) -> IResult<&str, Command> {
+    let (i, _) = tag("find")(i)?;
+    let (i, path) = opt(preceded(
+        space1,
+        verify(path, |p: &Utf8PathBuf| !p.as_str().starts_with('-')),
+    ))(i)?;
+    let (i, size) = opt(preceded(space1, size_filter))(i)?;
+
+    Ok((i, Command::Find { path, size }))
+}
+
+fn directory(i: &str) -> IResult<&str, DirectoryEntry> {
+    map(preceded(tuple((tag("dir"), space1)), name), |name| {
+        DirectoryEntry { name }
+    })(i)
+}
+
 fn file_entry(i: &str) -> IResult<&str, FileEntry> {
     map_res(
-        separated_pair(digit1, multispace1, not_line_ending),
-        |(size, name): (&str, &str)| {
-            size.parse::<usize>().map(|size| FileEntry {
-                size,
-                path: name.into(),
-            })
+        separated_pair(digit1, space1, name),
+        |(size, path): (&str, Utf8PathBuf)| {
+            size.parse::<usize>().map(|size| FileEntry { size, path })
         },
     )(i)
 }
@@ -79,27 +179,49 @@ fn listing(i: &str) -> IResult<&str, Listing> {
     ))(i)
 }
 
-fn ls(i: &str) -> IResult<&str, Command> {
-    let (i, _) = preceded(tag("ls"), multispace1)(i)?;
-    let (i, listings) = separated_list1(line_ending, listing)(i)?;
-
-    Ok((i, Command::Ls { listings }))
+fn command(i: &str) -> IResult<&str, Command> {
+    terminated(alt((cd, ls, pwd, du, tree, find)), space0)(i)
 }
 
-fn command(i: &str) -> IResult<&str, Command> {
-    alt((cd, ls))(i)
+// Output of a command, everything up to the next prompt
+fn output(i: &str) -> IResult<&str, Vec<&str>> {
+    many0(terminated(
+        verify(not_line_ending, |line: &str| {
+            !line.is_empty() && !line.starts_with('$')
+        }),
+        alt((line_ending, eof)),
+    ))(i)
 }
 
 fn command_line(i: &str) -> IResult<&str, Command> {
-    delimited(prompt, command, multispace0)(i)
+    let (i, mut command) = delimited(prompt, command, alt((line_ending, eof)))(i)?;
+    let (i, output) = output(i)?;
+
+    // Only the output of `ls` carries state, the rest is just what was shown at the time
+    if let Command::Ls { listings } = &mut command {
+        for line in output {
+            let (_, entry) = all_consuming(listing)(line)?;
+            listings.push(entry);
+        }
+    }
+
+    let (i, _) = multispace0(i)?;
+    Ok((i, command))
 }
 
-pub(super) fn parse_input() -> Result<Vec<Command>> {
-    let input = include_str!("../inputs/day7.txt");
-
-    let commands = all_consuming(many1(command_line))(input)
+pub(super) fn parse_input(input: &str) -> Result<Vec<Command>> {
+    let commands = all_consuming(preceded(multispace0, many0(command_line)))(input)
+        .map_err(|e| e.to_owned())
         .finish()
         .map(|(_, commands)| commands)?;
 
     Ok(commands)
 }
+
+pub(super) fn parse_command(line: &str) -> Result<Command> {
+    let (_, command) = all_consuming(delimited(space0, command, multispace0))(line)
+        .map_err(|e| e.to_owned())
+        .finish()?;
+
+    Ok(command)
+}
@@ -1,109 +1,282 @@
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    ops::RangeInclusive,
+};
+
 use anyhow::{Context, Result};
-use camino::Utf8PathBuf;
-use id_tree::{InsertBehavior, Node, Tree};
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{
+    filesystem::{Directory, FileSystem, Size},
+    INPUT,
+};
 
-use super::parsing::{parse_input, Command, FileEntry, Listing};
+pub fn solve_part1(input: &str) -> Result<usize> {
+    let fs = FileSystem::from_transcript(input)?;
+
+    let sum = fs
+        .directories()?
+        .into_iter()
+        .map(|dir| dir.size)
+        .filter(|&size| size <= 100_000)
+        .sum::<usize>();
+
+    Ok(sum)
+}
 
-#[derive(Debug)]
-struct FsEntry {
-    // Nice for showing solution
-    #[allow(unused)]
-    path: Utf8PathBuf,
-    size: usize,
+/// Size of the disk and the free space an update needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskPolicy {
+    pub capacity: usize,
+    pub required_free: usize,
 }
 
-fn total_size(tree: &Tree<FsEntry>, root: &Node<FsEntry>) -> Result<usize> {
-    let mut total = root.data().size;
-    for child in root.children() {
-        total += total_size(tree, tree.get(child)?)?;
+impl Default for DiskPolicy {
+    fn default() -> Self {
+        Self {
+            capacity: 70_000_000,
+            required_free: 30_000_000,
+        }
     }
-    Ok(total)
 }
 
-pub fn part1() -> Result<usize> {
-    let commands = parse_input()?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskError {
+    /// The files take up more space than the disk has.
+    Overfull { used: usize, capacity: usize },
+    /// Even an empty disk would not have the required free space.
+    TooSmall {
+        capacity: usize,
+        required_free: usize,
+    },
+}
+
+impl Display for DiskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DiskError::Overfull { used, capacity } => {
+                write!(f, "{used} bytes used but the disk only holds {capacity}")
+            }
+            DiskError::TooSmall {
+                capacity,
+                required_free,
+            } => write!(
+                f,
+                "{required_free} bytes can never be free on a disk of {capacity}"
+            ),
+        }
+    }
+}
 
-    let tree = make_tree(commands)?;
+impl std::error::Error for DiskError {}
 
-    let sum = tree
-        .traverse_pre_order(tree.root_node_id().context("Empty tree!")?)?
-        .filter(|node| !node.children().is_empty())
-        .map(|node| total_size(&tree, node).expect("Failed to calculate size"))
-        .filter(|&size| size <= 100_000)
-        .sum::<usize>();
+impl DiskPolicy {
+    /// How much has to be deleted from a disk with `used` bytes on it, `None` if nothing.
+    pub fn to_free(&self, used: usize) -> Result<Option<usize>, DiskError> {
+        let free = self.capacity.checked_sub(used).ok_or(DiskError::Overfull {
+            used,
+            capacity: self.capacity,
+        })?;
+        if self.required_free > self.capacity {
+            return Err(DiskError::TooSmall {
+                capacity: self.capacity,
+                required_free: self.required_free,
+            });
+        }
 
-    Ok(sum)
+        Ok(self.required_free.checked_sub(free).filter(|&n| n > 0))
+    }
 }
 
-pub fn part2() -> Result<usize> {
-    let total_space = 70_000_000_usize;
-    let needed_free_space = 30_000_000_usize;
+// What has to go, with every directory as a candidate for deleting it
+struct Deletion {
+    directories: Vec<Directory>,
+    need_to_free: usize,
+    // Index of the smallest directory that frees enough on its own
+    single: usize,
+}
+
+impl Deletion {
+    fn new(fs: &FileSystem, policy: &DiskPolicy) -> Result<Option<Self>> {
+        let directories = fs.directories()?;
+
+        // Children come before parents, so the root is last
+        let used_space = directories.last().context("Could not find root!")?.size;
+        let Some(need_to_free) = policy.to_free(used_space)? else {
+            return Ok(None);
+        };
+
+        // Deleting the root always works since the policy fits on an empty disk
+        let (single, _) = directories
+            .iter()
+            .enumerate()
+            .filter(|(_, dir)| dir.size >= need_to_free)
+            .min_by_key(|(_, dir)| dir.size)
+            .context("Found no suitable directories")?;
+
+        Ok(Some(Self {
+            directories,
+            need_to_free,
+            single,
+        }))
+    }
 
-    let commands = parse_input()?;
+    fn candidate(&self, fs: &FileSystem) -> (Utf8PathBuf, usize) {
+        let Directory { id, size, .. } = &self.directories[self.single];
+        (fs.path_of(id), *size)
+    }
+
+    fn set(&self, fs: &FileSystem) -> Vec<(Utf8PathBuf, usize)> {
+        let single_size = self.directories[self.single].size;
+
+        // Anything at least as big as the single best directory can't improve on it
+        let mut candidates = self
+            .directories
+            .iter()
+            .enumerate()
+            .filter(|(_, dir)| dir.size > 0 && dir.size < single_size)
+            .map(|(i, dir)| (i - dir.below..=i, dir.size))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut remaining = candidates
+            .iter()
+            .rev()
+            .scan(0, |sum, (_, size)| {
+                *sum += size;
+                Some(*sum)
+            })
+            .collect::<Vec<_>>();
+        remaining.reverse();
+
+        let mut search = SetSearch {
+            candidates: &candidates,
+            remaining,
+            need_to_free: self.need_to_free,
+            chosen: vec![],
+            best: (single_size, None),
+            budget: SEARCH_BUDGET,
+        };
+        search.run(0, 0);
+
+        let chosen = match search.best.1 {
+            Some(chosen) => chosen
+                .into_iter()
+                .map(|i| &self.directories[*candidates[i].0.end()])
+                .collect(),
+            None => vec![&self.directories[self.single]],
+        };
+        chosen
+            .into_iter()
+            .map(|dir| (fs.path_of(&dir.id), dir.size))
+            .collect()
+    }
+}
+
+/// The smallest directory whose deletion leaves enough free space, `None` if there already is.
+pub fn deletion_candidate(
+    fs: &FileSystem,
+    policy: &DiskPolicy,
+) -> Result<Option<(Utf8PathBuf, usize)>> {
+    Ok(Deletion::new(fs, policy)?.map(|deletion| deletion.candidate(fs)))
+}
 
-    let tree = make_tree(commands)?;
-    let root_id = tree.root_node_id().context("Could not find root!")?;
+/// Directories that together free enough space while deleting as little as possible, `None` if
+/// nothing needs deleting. Nested directories are never both picked. Past a fixed number of
+/// steps the search settles for the best set found so far, which is never worse than
+/// [`deletion_candidate`].
+pub fn deletion_set(
+    fs: &FileSystem,
+    policy: &DiskPolicy,
+) -> Result<Option<Vec<(Utf8PathBuf, usize)>>> {
+    Ok(Deletion::new(fs, policy)?.map(|deletion| deletion.set(fs)))
+}
+
+// Enough for every real input, while a root with many similar siblings gives up in milliseconds
+const SEARCH_BUDGET: usize = 1 << 20;
+
+// Branch and bound over the directories, largest first
+struct SetSearch<'a> {
+    // The span of `directories` each candidate's subtree covers, and its size
+    candidates: &'a [(RangeInclusive<usize>, usize)],
+    // Total size of `candidates[i..]`, an upper bound on what's still reachable
+    remaining: Vec<usize>,
+    need_to_free: usize,
+    chosen: Vec<usize>,
+    best: (usize, Option<Vec<usize>>),
+    budget: usize,
+}
 
-    let used_space = total_size(&tree, tree.get(root_id)?)?;
-    let free_space = total_space.checked_sub(used_space).unwrap();
+impl SetSearch<'_> {
+    fn run(&mut self, i: usize, freed: usize) {
+        if freed >= self.need_to_free {
+            if freed < self.best.0 {
+                self.best = (freed, Some(self.chosen.clone()));
+            }
+            return;
+        }
+        if i == self.candidates.len()
+            || freed + self.remaining[i] < self.need_to_free
+            || self.best.0 == self.need_to_free
+            || self.budget == 0
+        {
+            return;
+        }
+        self.budget -= 1;
 
-    let need_to_free = needed_free_space.checked_sub(free_space).unwrap();
+        // Subtrees are either nested or disjoint, so overlapping spans means nested directories
+        let (span, size) = &self.candidates[i];
+        let overlaps = self.chosen.iter().any(|&j| {
+            let other = &self.candidates[j].0;
+            span.start() <= other.end() && other.start() <= span.end()
+        });
+        if !overlaps && freed + size < self.best.0 {
+            self.chosen.push(i);
+            self.run(i + 1, freed + size);
+            self.chosen.pop();
+        }
+        self.run(i + 1, freed);
+    }
+}
 
-    let size_of_doomed_directory = tree
-        .traverse_pre_order(root_id)?
-        .filter(|node| !node.children().is_empty())
-        .map(|node| total_size(&tree, node).expect("Unable to calculate size!"))
-        .filter(|&size| size > need_to_free)
-        .min()
-        .context("Found no suitable directories")?;
+pub fn solve_part2(input: &str) -> Result<usize> {
+    let fs = FileSystem::from_transcript(input)?;
+    let (_, size_of_doomed_directory) = deletion_candidate(&fs, &DiskPolicy::default())?
+        .context("There is already enough free space")?;
 
     Ok(size_of_doomed_directory)
 }
 
-fn make_tree(commands: Vec<Command>) -> Result<Tree<FsEntry>> {
-    let mut tree = Tree::<FsEntry>::new();
-    let root = tree.insert(
-        Node::new(FsEntry {
-            path: "/".into(),
-            size: 0,
-        }),
-        InsertBehavior::AsRoot,
-    )?;
-    let mut curr = root;
-
-    for command in commands {
-        match command {
-            Command::Cd { directory } => match directory.as_str() {
-                "/" => (),
-                ".." => {
-                    curr = tree
-                        .get(&curr)?
-                        .parent()
-                        .context("Cannot get parent of un-inserted node, or root!")?
-                        .clone();
-                }
-                _ => {
-                    let node = Node::new(FsEntry {
-                        path: directory.clone(),
-                        size: 0,
-                    });
-                    curr = tree.insert(node, InsertBehavior::UnderNode(&curr))?;
-                }
-            },
-            Command::Ls { listings } => {
-                for listing in listings {
-                    if let Listing::File(FileEntry { size, path }) = listing {
-                        let node = Node::new(FsEntry { size, path });
-                        tree.insert(node, InsertBehavior::UnderNode(&curr))?;
-                    }
-                }
+/// Tree of cumulative sizes, the ten largest directories and what to delete for part 2.
+pub fn report(fs: &FileSystem) -> Result<String> {
+    let mut out = fs.tree(Some(Utf8Path::new("/")), true)?;
+
+    writeln!(out, "\nLargest directories:")?;
+    for (path, size) in fs.largest(10)? {
+        writeln!(out, "{:>8}  {path}", format!("{:#}", Size(size)))?;
+    }
+
+    match Deletion::new(fs, &DiskPolicy::default())? {
+        Some(deletion) => {
+            let (path, size) = deletion.candidate(fs);
+            writeln!(out, "\nDelete {path} to free {:#} ({size})", Size(size))?;
+            let set = deletion.set(fs);
+            let total = set.iter().map(|(_, size)| size).sum::<usize>();
+            writeln!(out, "Or delete these to free {:#} ({total}):", Size(total))?;
+            for (path, size) in set {
+                writeln!(out, "{:>8}  {path}", format!("{:#}", Size(size)))?;
             }
         }
+        None => writeln!(out, "\nThere is already enough free space")?,
     }
 
-    // let mut s = String::new();
-    // tree.write_formatted(&mut s)?;
-    // println!("{s}");
+    Ok(out)
+}
 
-    Ok(tree)
+pub fn part1() -> Result<usize> {
+    solve_part1(INPUT)
+}
+
+pub fn part2() -> Result<usize> {
+    solve_part2(INPUT)
 }
@@ -0,0 +1,60 @@
+use std::io::{self, Write};
+
+/// Kinds of forest to try the solvers on, from typical to worst case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forest {
+    /// Heights from a seeded generator, the same seed always growing the same forest.
+    Random { seed: u64 },
+    /// Every tree the same height, so each one blocks the view past it.
+    Flat(u8),
+    /// One step taller per tree from the edges in, levelling off at 9 ten trees from the edge.
+    Pyramid,
+    /// One step taller per tree from left to right, starting over after 9.
+    Ramp,
+}
+
+// SplitMix64, good enough to scatter heights around
+fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Forest {
+    pub fn grow(self, rows: usize, cols: usize) -> Vec<Vec<u8>> {
+        let mut state = match self {
+            Forest::Random { seed } => seed,
+            _ => 0,
+        };
+
+        (0..rows)
+            .map(|r| {
+                (0..cols)
+                    .map(|c| match self {
+                        Forest::Random { .. } => (next(&mut state) % 10) as u8,
+                        Forest::Flat(height) => height.min(9),
+                        Forest::Pyramid => [r, c, rows - 1 - r, cols - 1 - c]
+                            .into_iter()
+                            .min()
+                            .unwrap()
+                            .min(9) as u8,
+                        Forest::Ramp => (c % 10) as u8,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// A grid in the puzzle's format, one row of digits per line.
+pub fn write_grid(grid: &[Vec<u8>], mut w: impl Write) -> io::Result<()> {
+    for row in grid {
+        let line = row.iter().map(|&h| b'0' + h).collect::<Vec<_>>();
+        w.write_all(&line)?;
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
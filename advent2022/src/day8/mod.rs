@@ -0,0 +1,207 @@
+use anyhow::{bail, Context, Result};
+use nom::{
+    branch::alt,
+    character::complete::{line_ending, one_of},
+    combinator::{all_consuming, eof, map_res},
+    multi::many1,
+    sequence::terminated,
+    Finish, IResult,
+};
+
+pub mod generate;
+pub mod render;
+
+pub const INPUT: &str = include_str!("../inputs/day8.txt");
+
+fn parse_line(i: &str) -> IResult<&str, Vec<u8>> {
+    terminated(
+        many1(map_res(one_of("0123456789"), |num| {
+            num.to_string().parse::<u8>()
+        })),
+        alt((line_ending, eof)),
+    )(i)
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<Vec<u8>>> {
+    let result = all_consuming(many1(parse_line))(input.trim_end())
+        .map_err(|e| e.to_owned())
+        .finish()
+        .map(|(_, grid)| grid)?;
+
+    if let Some(row) = result.iter().position(|row| row.len() != result[0].len()) {
+        bail!(
+            "Row {} has {} trees, expected {}",
+            row + 1,
+            result[row].len(),
+            result[0].len()
+        );
+    }
+
+    Ok(result)
+}
+
+// Every line of sight across the grid, as the coordinates along it from the edge inwards
+fn lines_of_sight(grid: &[Vec<u8>]) -> impl Iterator<Item = Vec<(usize, usize)>> {
+    let (rows, cols) = (grid.len(), grid.first().map_or(0, Vec::len));
+
+    let across = (0..rows).flat_map(move |r| {
+        let line = (0..cols).map(|c| (r, c)).collect::<Vec<_>>();
+        [line.iter().rev().copied().collect(), line]
+    });
+    let down = (0..cols).flat_map(move |c| {
+        let line = (0..rows).map(|r| (r, c)).collect::<Vec<_>>();
+        [line.iter().rev().copied().collect(), line]
+    });
+
+    across.chain(down)
+}
+
+/// Whether each tree can be seen from outside the grid, from a running maximum along every line
+/// of sight.
+pub fn visibility_mask(grid: &[Vec<u8>]) -> Vec<Vec<bool>> {
+    let mut mask = grid
+        .iter()
+        .map(|row| vec![false; row.len()])
+        .collect::<Vec<_>>();
+
+    for line in lines_of_sight(grid) {
+        let mut tallest = None;
+        for (r, c) in line {
+            if tallest < Some(grid[r][c]) {
+                mask[r][c] = true;
+                tallest = Some(grid[r][c]);
+            }
+        }
+    }
+
+    mask
+}
+
+/// Scenic score of each tree. Along every line of sight a monotonic stack holds the trees that
+/// could still block the view back towards the edge.
+pub fn scenic_scores(grid: &[Vec<u8>]) -> Vec<Vec<usize>> {
+    let mut scores = grid
+        .iter()
+        .map(|row| vec![1; row.len()])
+        .collect::<Vec<_>>();
+
+    let mut blockers: Vec<(usize, u8)> = vec![];
+    for line in lines_of_sight(grid) {
+        blockers.clear();
+        for (i, (r, c)) in line.into_iter().enumerate() {
+            let height = grid[r][c];
+            while blockers.last().map_or(false, |&(_, h)| h < height) {
+                blockers.pop();
+            }
+            let distance = blockers.last().map_or(i, |&(j, _)| i - j);
+            scores[r][c] *= distance;
+            blockers.push((i, height));
+        }
+    }
+
+    scores
+}
+
+pub fn count_visible(grid: &[Vec<u8>]) -> usize {
+    visibility_mask(grid)
+        .into_iter()
+        .flatten()
+        .filter(|&v| v)
+        .count()
+}
+
+/// Position (row, column) and score of the tree with the best view.
+pub fn best_spot(grid: &[Vec<u8>]) -> Option<((usize, usize), usize)> {
+    scenic_scores(grid)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(r, row)| row.into_iter().enumerate().map(move |(c, s)| ((r, c), s)))
+        .max_by_key(|&(_, score)| score)
+}
+
+pub fn best_scenic_score(grid: &[Vec<u8>]) -> Option<usize> {
+    best_spot(grid).map(|(_, score)| score)
+}
+
+/// The original walk-every-ray solutions, O(n³) but obviously correct.
+pub mod reference {
+    const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+    fn plane(grid: &[Vec<u8>]) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..grid.len()).flat_map(|r| (0..grid[r].len()).map(move |c| (r, c)))
+    }
+
+    fn walk_towards_edges(
+        grid: &[Vec<u8>],
+        (r, c): (usize, usize),
+        (dr, dc): (isize, isize),
+    ) -> impl Iterator<Item = &u8> {
+        (1..).map_while(move |i| {
+            let r = r.checked_add_signed(dr * i)?;
+            let c = c.checked_add_signed(dc * i)?;
+            grid.get(r)?.get(c)
+        })
+    }
+
+    fn visible_trees_in_direction(
+        grid: &[Vec<u8>],
+        (r, c): (usize, usize),
+        (dr, dc): (isize, isize),
+    ) -> usize {
+        let line = walk_towards_edges(grid, (r, c), (dr, dc));
+
+        let mut total = 0;
+        let treehouse_height = grid[r][c];
+        for height in line {
+            total += 1;
+            if height >= &treehouse_height {
+                break;
+            }
+        }
+
+        total
+    }
+
+    fn scenic_score(grid: &[Vec<u8>], (r, c): (usize, usize)) -> usize {
+        DIRECTIONS
+            .into_iter()
+            .map(|(dr, dc)| visible_trees_in_direction(grid, (r, c), (dr, dc)))
+            .product()
+    }
+
+    pub fn count_visible(grid: &[Vec<u8>]) -> usize {
+        plane(grid)
+            .filter(|&(r, c)| {
+                let tree_height = grid[r][c];
+                DIRECTIONS.iter().any(|&(dr, dc)| {
+                    let mut trees_towards_edge = walk_towards_edges(grid, (r, c), (dr, dc));
+                    trees_towards_edge.all(|height| height < &tree_height)
+                })
+            })
+            .count()
+    }
+
+    pub fn best_scenic_score(grid: &[Vec<u8>]) -> Option<usize> {
+        plane(grid).map(|(r, c)| scenic_score(grid, (r, c))).max()
+    }
+}
+
+pub fn solve_part1(input: &str) -> Result<usize> {
+    let grid = parse_input(input)?;
+
+    Ok(count_visible(&grid))
+}
+
+pub fn solve_part2(input: &str) -> Result<usize> {
+    let grid = parse_input(input)?;
+
+    best_scenic_score(&grid).context("Could not find perfect spot!")
+}
+
+pub fn part1() -> Result<usize> {
+    solve_part1(INPUT)
+}
+
+pub fn part2() -> Result<usize> {
+    solve_part2(INPUT)
+}
@@ -0,0 +1,158 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use anyhow::Result;
+
+use super::{best_spot, scenic_scores, visibility_mask};
+
+type Rgb = [u8; 3];
+
+const SPOT: Rgb = [230, 30, 30];
+const RAY: Rgb = [250, 220, 60];
+
+/// One pixel per tree, row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    fn from_grid<T>(grid: &[Vec<T>], colour: impl Fn(&T) -> Rgb) -> Self {
+        Self {
+            width: grid.first().map_or(0, Vec::len),
+            height: grid.len(),
+            pixels: grid.iter().flatten().map(colour).collect(),
+        }
+    }
+
+    fn put(&mut self, (r, c): (usize, usize), colour: Rgb) {
+        self.pixels[r * self.width + c] = colour;
+    }
+
+    /// Every pixel blown up to a `factor` × `factor` square.
+    pub fn scaled(&self, factor: usize) -> Self {
+        let pixels = self
+            .pixels
+            .chunks(self.width.max(1))
+            .flat_map(|row| {
+                let row = row
+                    .iter()
+                    .flat_map(|&p| [p].repeat(factor))
+                    .collect::<Vec<_>>();
+                row.repeat(factor)
+            })
+            .collect();
+
+        Self {
+            width: self.width * factor,
+            height: self.height * factor,
+            pixels,
+        }
+    }
+
+    pub fn write_ppm(&self, mut w: impl Write) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.pixels.concat())
+    }
+
+    pub fn write_png(&self, w: impl Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()?
+            .write_image_data(&self.pixels.concat())?;
+
+        Ok(())
+    }
+
+    /// Two 24-bit background coloured columns per pixel, so they come out roughly square.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in self.pixels.chunks(self.width.max(1)) {
+            for [r, g, b] in row {
+                write!(out, "\x1b[48;2;{r};{g};{b}m  ").unwrap();
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+}
+
+fn lerp(from: Rgb, to: Rgb, t: f64) -> Rgb {
+    [0, 1, 2].map(|i| (from[i] as f64 + (to[i] as f64 - from[i] as f64) * t).round() as u8)
+}
+
+// Dark blue through purple and orange to pale yellow
+fn heat(t: f64) -> Rgb {
+    const STOPS: [Rgb; 4] = [
+        [10, 10, 40],
+        [120, 30, 120],
+        [240, 120, 30],
+        [255, 250, 190],
+    ];
+
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (t as usize).min(STOPS.len() - 2);
+    lerp(STOPS[i], STOPS[i + 1], t - i as f64)
+}
+
+// The best spot in red, and the trees it can see along each of its four rays in yellow
+fn mark_best_spot(image: &mut Image, grid: &[Vec<u8>]) {
+    let Some(((r, c), _)) = best_spot(grid) else {
+        return;
+    };
+
+    let height = grid[r][c];
+    for (dr, dc) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let ray = (1..).map_while(|i| {
+            let r = r.checked_add_signed(dr * i).filter(|&r| r < grid.len())?;
+            let c = c
+                .checked_add_signed(dc * i)
+                .filter(|&c| c < grid[r].len())?;
+            Some((r, c))
+        });
+        for (r, c) in ray {
+            let pixel = image.pixels[r * image.width + c];
+            image.put((r, c), lerp(pixel, RAY, 0.7));
+            if grid[r][c] >= height {
+                break;
+            }
+        }
+    }
+    image.put((r, c), SPOT);
+}
+
+/// Visible trees in green and hidden ones in grey, brighter the taller they are.
+pub fn visibility(grid: &[Vec<u8>]) -> Image {
+    let mask = visibility_mask(grid);
+    let cells = grid
+        .iter()
+        .zip(mask)
+        .map(|(heights, visible)| heights.iter().copied().zip(visible).collect())
+        .collect::<Vec<Vec<_>>>();
+
+    let mut image = Image::from_grid(&cells, |&(height, visible)| {
+        let t = height as f64 / 9.0;
+        match visible {
+            true => lerp([20, 80, 20], [120, 240, 120], t),
+            false => lerp([25, 25, 25], [110, 110, 110], t),
+        }
+    });
+    mark_best_spot(&mut image, grid);
+    image
+}
+
+/// Scenic scores on a square root scale, so the few huge ones don't wash out the rest.
+pub fn heatmap(grid: &[Vec<u8>]) -> Image {
+    let scores = scenic_scores(grid);
+    let best = scores.iter().flatten().max().copied().unwrap_or(0).max(1) as f64;
+
+    let mut image = Image::from_grid(&scores, |&score| heat((score as f64 / best).sqrt()));
+    mark_best_spot(&mut image, grid);
+    image
+}
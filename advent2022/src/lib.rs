@@ -3,6 +3,10 @@
 #![feature(drain_filter)]
 
 pub mod day1;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
 pub mod day2;
 pub mod day3;
 pub mod day4;
@@ -10,10 +14,9 @@ pub mod day5;
 pub mod day6;
 pub mod day7;
 pub mod day8;
-pub mod day13;
-pub mod day14;
-pub mod day15;
-pub mod day16;
+pub mod registry;
 #[cfg(test)]
 mod tests;
 mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
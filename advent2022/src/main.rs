@@ -1,67 +1,168 @@
-use anyhow::Result;
+use std::{env, fs, io};
 
-use advent::*;
+use anyhow::{bail, ensure, Context, Result};
 
-fn main() -> Result<()> {
-    // println!("Day 1:");
-    // println!("\tPart 1, max calories: {}", day1::part1()?);
-    // println!("\tPart 2, total calories of top 3: {}", day1::part2()?);
-    //
-    // println!("Day 2:");
-    // println!("\tPart 1, total score: {}", day2::part1()?);
-    // println!("\tPart 2, total score: {}", day2::part2()?);
-    //
-    // println!("Day 3:");
-    // println!("\tPart 1, total priority: {}", day3::part1());
-    // println!("\tPart 2, total priority: {}", day3::part2());
-    //
-    // println!("Day 4:");
-    // println!("\tPart 1, total overlaps: {}", day4::part1());
-    // println!("\tPart 2, partial overlaps: {}", day4::part2());
-    //
-    // println!("Day 5:");
-    // println!("\tPart 1, top of each stack: {}", day5::part1()?);
-    // println!("\tPart 2, top of each stack: {}", day5::part2()?);
-    //
-    // println!("Day 6:");
-    // println!(
-    //     "\tPart 1, index of 4 unique: {}",
-    //     day6::part1().context("Could not find run of 4")?
-    // );
-    // println!(
-    //     "\tPart 2, index of 14 unique: {}",
-    //     day6::part2().context("Could not find run of 4")?
-    // );
+use advent::{
+    day1, day13, day2, day4, day5, day6, day7, day8,
+    registry::{self, Day},
+};
+
+fn run_day(day: &Day, input: &str) -> Result<()> {
+    println!("Day {}:", day.number);
+    for (i, part) in day.parts.iter().enumerate() {
+        let answer = (part.solve)(input)?;
+        println!("\tPart {}, {}: {answer}", i + 1, part.description);
+    }
+
+    Ok(())
+}
 
-    // println!("Day 7:");
-    // println!(
-    //     "\tPart 1, sum of directories smaller than 100000: {}",
-    //     day7::part1()?
-    // );
-    // println!(
-    //     "\tPart 2, size of directory to delete: {}",
-    //     day7::part2()?
-    // );
+fn read_input(path: Option<String>, default: &str) -> Result<String> {
+    match path {
+        Some(path) => fs::read_to_string(&path).with_context(|| format!("Failed to read {path}")),
+        None => Ok(default.to_string()),
+    }
+}
 
-    // println!("Day 8:");
-    // println!("\tPart 1, number of visible trees: {}", day8::part1()?);
-    // println!("\tPart 2, scenic score for perfect spot: {}", day8::part2()?);
+fn run_tool(tool: &str, mut args: impl Iterator<Item = String>) -> Result<()> {
+    match tool {
+        // Score the day 2 strategy guide round by round, `strategy [INPUT]`
+        "strategy" => {
+            let input = read_input(args.next(), day2::INPUT)?;
+            print!(
+                "{}",
+                day2::report(&input, &day2::Rules::rock_paper_scissors())?
+            );
+        }
+        // Every start-of-packet marker in a signal of any length, `signal [N] [INPUT|-]`
+        "signal" => {
+            let n = args.next().map_or(Ok(4), |n| n.parse())?;
+            ensure!(n > 0, "Markers must be at least one symbol long");
+            let reader: Box<dyn io::Read> = match args.next().as_deref() {
+                Some("-") => Box::new(io::stdin().lock()),
+                Some(path) => Box::new(
+                    fs::File::open(path).with_context(|| format!("Failed to read {path}"))?,
+                ),
+                None => Box::new(day6::INPUT.as_bytes()),
+            };
+            for position in day6::markers(reader, n) {
+                println!("{}", position?);
+            }
+        }
+        // Which day 4 sections are busiest and which pairs could merge, `sections [INPUT]`
+        "sections" => {
+            let input = read_input(args.next(), day4::INPUT)?;
+            print!("{}", day4::report(&input)?);
+        }
+        // Step through the day 5 crane moves, `crane [PART] [INPUT]`
+        "crane" => {
+            let part = args.next();
+            let input = read_input(args.next(), day5::INPUT)?;
+            let (stdin, stdout) = (io::stdin().lock(), io::stdout());
+            match part.as_deref() {
+                None | Some("1") => day5::Replay::new(&input, day5::CrateMover9000)?
+                    .run_interactive(stdin, stdout)?,
+                Some("2") => day5::Replay::new(&input, day5::CrateMover9001)?
+                    .run_interactive(stdin, stdout)?,
+                Some(part) => bail!("Unknown part {part}"),
+            }
+        }
+        // Explore the day 7 filesystem with a small shell, `fs [INPUT]`
+        "fs" => {
+            let input = read_input(args.next(), day7::INPUT)?;
+            day7::FileSystem::from_transcript(&input)?
+                .run_interactive(io::stdin().lock(), io::stdout())?;
+        }
+        // Sizes of the day 7 filesystem and what to delete, `disk [INPUT]`
+        "disk" => {
+            let input = read_input(args.next(), day7::INPUT)?;
+            print!(
+                "{}",
+                day7::report(&day7::FileSystem::from_transcript(&input)?)?
+            );
+        }
+        // Picture the day 8 forest, `forest visibility|heatmap [OUTPUT.ppm|OUTPUT.png|-] [INPUT]`
+        "forest" => {
+            let mode = args.next();
+            let output = args.next();
+            let grid = day8::parse_input(&read_input(args.next(), day8::INPUT)?)?;
+            let image = match mode.as_deref() {
+                Some("visibility") => day8::render::visibility(&grid),
+                Some("heatmap") => day8::render::heatmap(&grid),
+                _ => bail!("Expected visibility or heatmap"),
+            };
+            match output.as_deref() {
+                None | Some("-") => print!("{}", image.to_ansi()),
+                Some(path) if path.ends_with(".png") => {
+                    image.scaled(8).write_png(fs::File::create(path)?)?
+                }
+                Some(path) => image.scaled(8).write_ppm(fs::File::create(path)?)?,
+            }
+        }
+        // Grow a day 8 puzzle input, `grow random|flat|pyramid|ramp ROWS COLS [SEED|HEIGHT]`
+        "grow" => {
+            let kind = args.next();
+            let mut size =
+                || -> Result<usize> { Ok(args.next().context("Expected ROWS and COLS")?.parse()?) };
+            let (rows, cols) = (size()?, size()?);
+            let forest = match kind.as_deref() {
+                Some("random") => day8::generate::Forest::Random {
+                    seed: args.next().map_or(Ok(0), |seed| seed.parse())?,
+                },
+                Some("flat") => {
+                    let height = args.next().map_or(Ok(5), |height| height.parse())?;
+                    if height > 9 {
+                        bail!("Trees are at most 9 tall, not {height}");
+                    }
+                    day8::generate::Forest::Flat(height)
+                }
+                Some("pyramid") => day8::generate::Forest::Pyramid,
+                Some("ramp") => day8::generate::Forest::Ramp,
+                _ => bail!("Expected random, flat, pyramid or ramp"),
+            };
+            day8::generate::write_grid(&forest.grow(rows, cols), io::stdout().lock())?;
+        }
+        // Why each day 13 pair is or isn't in order, `packets [INPUT]`
+        "packets" => {
+            let input = read_input(args.next(), day13::INPUT)?;
+            print!("{}", day13::explain_pairs(&input)?);
+        }
+        // Stream a calorie log of any size, `elves [K] [INPUT]`
+        "elves" => {
+            let k = args.next().map_or(Ok(3), |k| k.parse())?;
+            let top = match args.next() {
+                Some(path) => {
+                    let file =
+                        fs::File::open(&path).with_context(|| format!("Failed to read {path}"))?;
+                    day1::top_k(io::BufReader::new(file), k)?
+                }
+                None => day1::top_k(day1::INPUT.as_bytes(), k)?,
+            };
+            for elf in top {
+                println!("Elf {}: {} calories", elf.index + 1, elf.calories);
+            }
+        }
+        _ => bail!("Unknown tool {tool}"),
+    }
 
-    // println!("Day 13:");
-    // println!("\tPart 1, sum of out of order indices: {}", day13::part1()?);
-    // println!("\tPart 2, product of decoder indices: {}", day13::part2()?);
+    Ok(())
+}
 
-    // println!("Day 14:");
-    // println!("\tPart 1, grains until cave in: {}", day14::part1()?);
-    // println!("\tPart 2, grains until cave full: {}", day14::part2()?);
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
 
-    // println!("Day 15:");
-    // println!("\tPart 1, covered squares at row 10: {}", day15::part1()?);
-    // println!("\tPart 2, tuning frequency: {}", day15::part2()?);
+    let Some(day) = args.next() else {
+        for day in registry::DAYS {
+            run_day(day, day.input)?;
+        }
+        return Ok(());
+    };
 
-    println!("Day 16:");
-    println!("\tPart 1, max pressure released: {}", day16::part1()?);
-    println!("\tPart 1, max pressure released with elephant: {}", day16::part2()?);
+    let Ok(day) = day.parse() else {
+        return run_tool(&day, args);
+    };
+    let day = registry::day(day).with_context(|| format!("Day {day} is not solved"))?;
+    let input = read_input(args.next(), day.input)?;
 
-    Ok(())
+    run_day(day, &input)
 }
@@ -0,0 +1,134 @@
+use std::{fmt::Display, time::Duration};
+
+use anyhow::{bail, Context, Result};
+
+use crate::*;
+
+pub struct Part {
+    pub description: &'static str,
+    pub solve: fn(&str) -> Result<String>,
+}
+
+pub struct Day {
+    pub number: u32,
+    pub input: &'static str,
+    pub parts: [Part; 2],
+}
+
+pub struct Solution {
+    pub answer: String,
+    pub elapsed: Duration,
+}
+
+trait IntoAnswer {
+    fn into_answer(self) -> Result<String>;
+}
+
+impl<T: Display> IntoAnswer for Result<T> {
+    fn into_answer(self) -> Result<String> {
+        self.map(|answer| answer.to_string())
+    }
+}
+
+impl<T: Display> IntoAnswer for Option<T> {
+    fn into_answer(self) -> Result<String> {
+        self.map(|answer| answer.to_string())
+            .context("No answer found")
+    }
+}
+
+macro_rules! day {
+    ($number:literal, $day:ident, $part1:literal, $part2:literal) => {
+        Day {
+            number: $number,
+            input: $day::INPUT,
+            parts: [
+                Part {
+                    description: $part1,
+                    solve: |input| $day::solve_part1(input).into_answer(),
+                },
+                Part {
+                    description: $part2,
+                    solve: |input| $day::solve_part2(input).into_answer(),
+                },
+            ],
+        }
+    };
+}
+
+pub static DAYS: &[Day] = &[
+    day!(1, day1, "max calories", "total calories of top 3"),
+    day!(2, day2, "total score", "total score"),
+    day!(3, day3, "total priority", "total priority"),
+    day!(4, day4, "total overlaps", "partial overlaps"),
+    day!(5, day5, "top of each stack", "top of each stack"),
+    day!(6, day6, "index of 4 unique", "index of 14 unique"),
+    day!(
+        7,
+        day7,
+        "sum of directories smaller than 100000",
+        "size of directory to delete"
+    ),
+    day!(
+        8,
+        day8,
+        "number of visible trees",
+        "scenic score for perfect spot"
+    ),
+    day!(
+        13,
+        day13,
+        "sum of out of order indices",
+        "product of decoder indices"
+    ),
+    day!(14, day14, "grains until cave in", "grains until cave full"),
+    day!(
+        15,
+        day15,
+        "covered squares at row 2000000",
+        "tuning frequency"
+    ),
+    day!(
+        16,
+        day16,
+        "max pressure released",
+        "max pressure released with elephant"
+    ),
+];
+
+pub fn day(number: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
+
+pub fn solve(day_number: u32, part: u32, input: &str) -> Result<Solution> {
+    let day = day(day_number).with_context(|| format!("Day {day_number} is not solved"))?;
+    let Some(part) = part.checked_sub(1).and_then(|i| day.parts.get(i as usize)) else {
+        bail!("Part {part} does not exist, expected 1 or 2");
+    };
+
+    let (answer, elapsed) = timed(|| (part.solve)(input));
+
+    Ok(Solution {
+        answer: answer?,
+        elapsed,
+    })
+}
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = std::time::Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+// `Instant` is unsupported on wasm32-unknown-unknown, so use the browser's clock instead
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let performance = web_sys::window()
+        .and_then(|window| window.performance())
+        .expect("No performance clock available");
+    let start = performance.now();
+    let result = f();
+    let elapsed = Duration::from_secs_f64((performance.now() - start).max(0.0) / 1000.0);
+    (result, elapsed)
+}
@@ -14,10 +14,273 @@ macro_rules! test_day {
     };
 }
 
+mod records {
+    use crate::utils::records;
+
+    #[test]
+    fn line_endings() {
+        let records = |input| records(input).collect::<Vec<_>>();
+
+        let expected = [(1, "1\n2"), (4, "  3"), (7, "4")];
+        assert_eq!(records("1\n2\n\n  3\n\n\n4\n"), expected);
+        assert_eq!(records("1\n2\n\n  3\n\n\n4"), expected);
+        assert_eq!(records("1\n2\n \t\n  3 \n\n\n4\n\n\n"), expected);
+        assert_eq!(
+            records("1\r\n2\r\n\r\n  3\r\n \r\n\r\n4\r\n"),
+            [(1, "1\r\n2"), (4, "  3"), (7, "4")]
+        );
+        assert_eq!(records(" \n\r\n"), []);
+    }
+
+    #[test]
+    fn read_from_reader() {
+        use crate::utils::read_records;
+
+        for input in [
+            "1\n2\n\n  3\n\n\n4\n",
+            "1\r\n2\r\n \r\n  3 \n\n\n4",
+            " \n\r\n",
+            "",
+        ] {
+            let read = read_records(input.as_bytes())
+                .collect::<std::io::Result<Vec<_>>>()
+                .unwrap();
+            let expected = records(input)
+                .map(|(line, record)| (line, record.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(read, expected);
+        }
+    }
+
+    #[test]
+    fn grouped_days_accept_crlf() {
+        let crlf = |input: &str| input.replace('\n', "\r\n");
+        assert_eq!(
+            crate::day1::solve_part2(&crlf(crate::day1::INPUT)).unwrap(),
+            199172
+        );
+        assert_eq!(
+            crate::day13::solve_part2(&crlf(crate::day13::INPUT)).unwrap(),
+            24477
+        );
+        assert_eq!(
+            crate::day5::solve_part2(&crlf(crate::day5::INPUT)).unwrap(),
+            "JNRSCDWPP"
+        );
+    }
+}
+
 test_day!(day1, Ok(66616), Ok(199172));
+
+mod day1_top_k {
+    use crate::day1::{top_k, Elf};
+
+    const LOG: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+
+    #[test]
+    fn top_elves() {
+        let top = |k| {
+            top_k(LOG.as_bytes(), k)
+                .unwrap()
+                .into_iter()
+                .map(|Elf { index, calories }| (index, calories))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(top(3), [(3, 24000), (2, 11000), (4, 10000)]);
+        assert_eq!(top(10).len(), 5);
+        assert_eq!(top(0), []);
+        assert_eq!(top(usize::MAX).len(), 5);
+
+        let ties = top_k("5\n\n5\n\r\n\n5\r\n".as_bytes(), 2).unwrap();
+        assert_eq!(ties.iter().map(|elf| elf.index).collect::<Vec<_>>(), [0, 1]);
+    }
+
+    #[test]
+    fn bad_line() {
+        let err = top_k("1\n\n2\nthree\n".as_bytes(), 1).unwrap_err();
+        assert!(err.to_string().starts_with("Line 4:"), "{err}");
+    }
+}
+
 test_day!(day2, Ok(14264), Ok(12382));
-test_day!(day3, 8185, 2817);
-test_day!(day4, 485, 857);
+
+mod day2_rules {
+    use crate::day2::{Outcome, Rules, Shape};
+
+    #[test]
+    fn derived_responses() {
+        let rules = Rules::rock_paper_scissors();
+        for theirs in (0..3).map(Shape) {
+            for wanted in [Outcome::Lose, Outcome::Draw, Outcome::Win] {
+                let mine = rules.response_for(theirs, wanted).unwrap();
+                assert_eq!(rules.outcome(mine, theirs), wanted);
+            }
+        }
+        assert_eq!(rules.response_for(Shape(0), Outcome::Win), Some(Shape(1)));
+    }
+
+    #[test]
+    fn lizard_spock() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        let rock = rules.opponent("A").unwrap();
+        assert_eq!(rules.name(rock), "Rock");
+
+        // Paper and Spock both beat rock, Spock scores more
+        assert_eq!(rules.response_for(rock, Outcome::Win), Some(Shape(4)));
+        assert_eq!(rules.outcome(Shape(3), Shape(4)), Outcome::Win);
+        assert_eq!(rules.score(Shape(2), Shape(4)), 3);
+        assert_eq!(rules.response("V").unwrap(), rock);
+    }
+
+    #[test]
+    fn custom_tables() {
+        let rules = Rules::new(&[("Fire", 10), ("Water", 20)], &[("Water", "Fire")])
+            .unwrap()
+            .with_letters("fw", "FW")
+            .unwrap()
+            .with_outcome_letters('l', 'd', 'w')
+            .with_outcome_scores(0, 1, 100);
+
+        assert_eq!(rules.score(rules.response("W").unwrap(), Shape(0)), 120);
+        assert_eq!(rules.requirement("w").unwrap(), Outcome::Win);
+        assert_eq!(rules.response_for(Shape(1), Outcome::Win), None);
+        assert!(rules.opponent("A").is_err());
+
+        assert!(Rules::new(&[("Rock", 1)], &[("Rock", "Rock")]).is_err());
+        assert!(Rules::new(&[("A", 1), ("B", 2)], &[("A", "B"), ("B", "A")]).is_err());
+        assert!(Rules::new(&[("A", 1)], &[("A", "C")]).is_err());
+        assert!(Rules::rock_paper_scissors()
+            .with_letters("AB", "XYZ")
+            .is_err());
+    }
+
+    #[test]
+    fn report() {
+        use crate::day2::{best_assignment, compute_score, rounds, Input, Tally};
+
+        let rules = Rules::rock_paper_scissors();
+        let input = "A Y\nB X\nC Z\n";
+
+        let guide = rounds(input, &rules, &Input::Guide).unwrap();
+        assert_eq!(guide.iter().map(|r| r.score).collect::<Vec<_>>(), [8, 1, 6]);
+        let plan = rounds(input, &rules, &Input::Plan).unwrap();
+        assert_eq!(plan.iter().map(|r| r.score).collect::<Vec<_>>(), [4, 1, 7]);
+        let one_each = Tally {
+            wins: 1,
+            draws: 1,
+            losses: 1,
+        };
+        assert_eq!(Tally::of(&guide), one_each);
+        assert_eq!(Tally::of(&plan), one_each);
+
+        // Reading X as scissors and Z as rock wins all three
+        let (shapes, score) = best_assignment(input, &rules).unwrap();
+        assert_eq!(shapes, [Shape(2), Shape(1), Shape(0)]);
+        assert_eq!(score, 24);
+        let assignment = Input::Assignment(shapes);
+        assert_eq!(compute_score(input, &rules, &assignment).unwrap(), 24);
+
+        let (_, best) = best_assignment(crate::day2::INPUT, &rules).unwrap();
+        assert!(best >= 14264);
+
+        assert!(rounds("A Y\nB\n", &rules, &Input::Guide)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Line 2"));
+        assert!(rounds(input, &rules, &Input::Assignment(vec![Shape(0)])).is_err());
+    }
+}
+
+test_day!(day3, Ok(8185), Ok(2817));
+
+mod day3_rucksacks {
+    use crate::day3::{group_priorities, solve_part1, solve_part2};
+
+    const EXAMPLE: &str = "\
+vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
+
+    #[test]
+    fn example() {
+        assert_eq!(solve_part1(EXAMPLE).unwrap(), 157);
+        assert_eq!(solve_part2(EXAMPLE).unwrap(), 70);
+        assert_eq!(group_priorities("aA\nAb\n", 2).unwrap(), 27);
+    }
+
+    #[test]
+    fn malformed() {
+        let error = |result: anyhow::Result<u32>| format!("{:#}", result.unwrap_err());
+
+        assert_eq!(
+            error(solve_part1("abca\nabc\n")),
+            "Line 2: Odd number of items"
+        );
+        assert_eq!(error(solve_part1("abcd\n")), "Line 1: No item in common");
+        assert_eq!(
+            error(solve_part1("abab\n")),
+            "Line 1: More than one item in common: ab"
+        );
+        assert_eq!(error(solve_part1("a1a1\n")), "Line 1: '1' is not an item");
+        assert!(error(group_priorities("aA\nAb\nc\n", 2)).ends_with("only 1 of 2 rucksacks"));
+        assert!(group_priorities(EXAMPLE, 0).is_err());
+    }
+}
+test_day!(day4, Ok(485), Ok(857));
+
+mod day4_sections {
+    use crate::day4::{busiest_sections, mergeable, parse_input, solve_part1, solve_part2};
+
+    const EXAMPLE: &str = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n";
+
+    #[test]
+    fn inclusive() {
+        assert_eq!(solve_part1(EXAMPLE).unwrap(), 2);
+        assert_eq!(solve_part2(EXAMPLE).unwrap(), 4);
+        // Touching at one section overlaps, sitting next to each other doesn't
+        assert_eq!(solve_part2("1-3,3-5\n1-3,4-5\n").unwrap(), 1);
+        assert_eq!(solve_part1("3-3,3-3\n").unwrap(), 1);
+
+        assert_eq!(
+            format!("{:#}", parse_input("1-3,4-5\n5-3,1-1\n").unwrap_err()),
+            "Line 2: 5-3 runs backwards"
+        );
+        assert!(parse_input("1-3,4\n").is_err());
+        assert!(parse_input("1-3,4-5x\n").is_err());
+    }
+
+    #[test]
+    fn roster() {
+        let pairs = parse_input(EXAMPLE).unwrap();
+        assert_eq!(busiest_sections(&pairs), (8, vec![6..=6]));
+        assert_eq!(
+            busiest_sections(&parse_input("1-2,3-4\n").unwrap()),
+            (1, vec![1..=4])
+        );
+        assert_eq!(
+            busiest_sections(&parse_input("2-4,6-8\n2-3,4-5\n").unwrap()),
+            (2, vec![2..=4])
+        );
+        assert_eq!(
+            busiest_sections(&parse_input("1-2,4-5\n").unwrap()),
+            (1, vec![1..=2, 4..=5])
+        );
+        assert_eq!(busiest_sections(&[]), (0, vec![]));
+
+        assert_eq!(
+            mergeable(&pairs),
+            [(2, 2..=5), (3, 5..=9), (4, 2..=8), (5, 4..=6), (6, 2..=8)]
+        );
+
+        let edge = parse_input("4294967295-4294967295,1-3\n1-3,4294967294-4294967295\n").unwrap();
+        assert_eq!(mergeable(&edge), []);
+        assert!(crate::day4::report("4294967294-4294967294,4294967295-4294967295\n").is_ok());
+    }
+}
 
 #[cfg(test)]
 mod day5 {
@@ -30,12 +293,590 @@ mod day5 {
     fn part2() {
         assert!(matches!(crate::day5::part2().as_deref(), Ok("JNRSCDWPP")));
     }
+
+    #[test]
+    fn replay() {
+        let mut replay =
+            crate::day5::Replay::new(crate::day5::INPUT, crate::day5::CrateMover9001).unwrap();
+        replay.jump_to(replay.len());
+        assert_eq!(replay.tops().unwrap(), "JNRSCDWPP");
+
+        let after_10 = {
+            replay.jump_to(10);
+            replay.to_string()
+        };
+        assert!(replay.forward());
+        assert!(replay.back());
+        assert_eq!(replay.to_string(), after_10);
+    }
+
+    #[test]
+    fn capacity_limited() {
+        use crate::day5::{parse_input, simulate, CapacityLimited};
+
+        let (stacks, moves) = parse_input(crate::day5::INPUT).unwrap();
+        let tops = |capacity| {
+            simulate(stacks.clone(), &moves, &CapacityLimited { capacity })
+                .unwrap()
+                .tops()
+                .unwrap()
+        };
+        assert_eq!(tops(1), "TWSGQHNHL");
+        assert_eq!(tops(u32::MAX), "JNRSCDWPP");
+    }
+
+    #[test]
+    fn wide_drawing() {
+        let input = [
+            "                                        [Jk]",
+            "[A]                                     [J]",
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I]     [Lm]",
+            " 1   2   3   4   5   6   7   8   9  10  11",
+            "",
+            "move 1 from 1 to 10",
+        ]
+        .join("\n");
+        assert_eq!(crate::day5::solve_part2(&input).unwrap(), "ABCDEFGHIAJk");
+
+        // Nothing left on top of stack 1
+        let emptied = input.replace("move 1", "move 2");
+        let error = crate::day5::solve_part2(&emptied).unwrap_err();
+        assert_eq!(error.to_string(), "Stack 1 is empty");
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn round_trip(
+            stacks in proptest::collection::vec(
+                proptest::collection::vec("[A-Za-z0-9]{1,3}", 0..6),
+                1..12,
+            ),
+            moves in proptest::collection::vec((0..100_u32, 0..12_usize, 0..12_usize), 0..10),
+        ) {
+            use crate::day5::{parse_input, serialize, Crate, Move, Stacks};
+
+            let stacks = Stacks(
+                stacks
+                    .into_iter()
+                    .map(|stack| stack.into_iter().map(Crate).collect())
+                    .collect(),
+            );
+            let moves = moves
+                .into_iter()
+                .map(|(amount, from, to)| Move { amount, from, to })
+                .collect::<Vec<_>>();
+
+            let (parsed_stacks, parsed_moves) = parse_input(&serialize(&stacks, &moves)).unwrap();
+            proptest::prop_assert_eq!(parsed_stacks, stacks);
+            proptest::prop_assert_eq!(parsed_moves, moves);
+        }
+    }
+
+    #[test]
+    fn invalid_moves() {
+        let input = "[A]\n 1 \n\nmove 1 from 1 to 2";
+        assert!(crate::day5::solve_part1(input).is_err());
+        let input = "[A]\n 1 \n\nmove 2 from 1 to 1";
+        assert!(crate::day5::solve_part1(input).is_err());
+        let input = "[A]\n 1 \n\nmove 1 from 0 to 1";
+        assert!(crate::day5::solve_part1(input).is_err());
+    }
 }
 
 test_day!(day6, Some(1155), Some(2789));
+
+mod day6_markers {
+    use itertools::Itertools;
+
+    use crate::day6::{find_n_unique, markers};
+
+    fn naive(input: &[u8], n: usize) -> Vec<usize> {
+        input
+            .windows(n)
+            .positions(|window| window.iter().all_unique())
+            .map(|i| i + n)
+            .collect()
+    }
+
+    #[test]
+    fn examples() {
+        let signal = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        assert_eq!(find_n_unique(signal.chars(), 4), Some(7));
+        assert_eq!(find_n_unique(signal.chars(), 14), Some(19));
+        assert_eq!(find_n_unique("aaaa".chars(), 2), None);
+        assert_eq!(find_n_unique("ab".chars(), 3), None);
+        assert_eq!(find_n_unique("éèé".chars(), 2), Some(2));
+        assert_eq!(find_n_unique([7, 7, 300, 7], 2), Some(3));
+        assert_eq!(find_n_unique(signal.chars(), 0), None);
+
+        let all = |input: &str, n| markers(input.as_bytes(), n).collect::<Result<Vec<_>, _>>();
+        assert_eq!(all("abcd", 2).unwrap(), [2, 3, 4]);
+        assert_eq!(all("aabaa", 2).unwrap(), [3, 4]);
+        assert_eq!(all("abab", 3).unwrap(), []);
+        assert_eq!(all("abcd", 0).unwrap(), []);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn matches_naive(input in proptest::collection::vec(0..6_u8, 0..200), n in 1..8_usize) {
+            let found = markers(input.as_slice(), n).collect::<Result<Vec<_>, _>>().unwrap();
+            proptest::prop_assert_eq!(&found, &naive(&input, n));
+            proptest::prop_assert_eq!(find_n_unique(input, n), found.first().copied());
+        }
+    }
+}
 test_day!(day7, Ok(1454188), Ok(4183246));
+
+mod day7_filesystem {
+    use crate::day7::FileSystem;
+
+    const TRANSCRIPT: &str = "\
+$ cd /
+$ ls
+dir a.b
+14848514 b.txt
+$ cd a.b
+$ ls
+dir my-dir
+29116 f
+$ cd my-dir
+$ ls
+584 i
+$ pwd
+/a.b/my-dir
+$ cd /a.b/../a.b/my-dir/..
+$ ls
+dir my-dir
+29116 f
+";
+
+    #[test]
+    fn commands() {
+        let mut fs = FileSystem::from_transcript(TRANSCRIPT).unwrap();
+        assert_eq!(fs.execute("cd a.b/my-dir").unwrap(), "");
+        assert_eq!(fs.execute("pwd").unwrap(), "/a.b/my-dir\n");
+        assert_eq!(
+            fs.execute("du /").unwrap(),
+            "584\t/a.b/my-dir\n29700\t/a.b\n14878214\t/\n"
+        );
+        assert_eq!(fs.execute("find / -size +20k").unwrap(), "/a.b/f\n/b.txt\n");
+        assert_eq!(fs.execute("find .. -size -1k").unwrap(), "/a.b/my-dir/i\n");
+        assert!(fs.execute("find / -size 99999999999999G").is_err());
+        assert_eq!(
+            fs.execute("tree ..").unwrap(),
+            "- a.b (dir, size=29700)\n  - my-dir (dir, size=584)\n    - i (file, size=584)\n  - f (file, size=29116)\n"
+        );
+        assert_eq!(
+            fs.execute("du -h /a.b").unwrap(),
+            "584\t/a.b/my-dir\n29K\t/a.b\n"
+        );
+        assert!(fs.execute("cd nowhere").is_err());
+        assert!(fs.execute("cd i").is_err());
+    }
+
+    #[test]
+    fn reports() {
+        use crate::day7::{deletion_candidate, Size};
+
+        assert_eq!(format!("{:#}", Size(1023)), "1023");
+        assert_eq!(format!("{:#}", Size(1536)), "1.5K");
+        assert_eq!(format!("{:#}", Size(42 << 20)), "42M");
+        assert_eq!(format!("{}", Size(42 << 20)), "44040192");
+
+        let fs = FileSystem::from_transcript(crate::day7::INPUT).unwrap();
+        let (path, size) = deletion_candidate(&fs, &Default::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!((path.as_str(), size), ("/cmvqf/gccnrw/wvq", 4183246));
+        let largest = fs.largest(3).unwrap();
+        assert_eq!(largest[0].0, "/");
+        assert!(largest.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn disk_policy() {
+        use std::fmt::Write;
+
+        use crate::day7::{deletion_candidate, deletion_set, DiskError, DiskPolicy};
+
+        let fs = FileSystem::from_transcript(TRANSCRIPT).unwrap();
+        let policy = |capacity, required_free| DiskPolicy {
+            capacity,
+            required_free,
+        };
+
+        assert!(deletion_candidate(&fs, &policy(30_000_000, 10_000))
+            .unwrap()
+            .is_none());
+        let err = deletion_candidate(&fs, &policy(10_000, 0)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(DiskError::Overfull { .. })
+        ));
+        let err = deletion_candidate(&fs, &policy(20_000_000, 30_000_000)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(DiskError::TooSmall { .. })
+        ));
+
+        // Freeing 600 bytes takes all of `/a.b`, `/a.b/my-dir` alone is too small
+        let tight = policy(14_878_214 + 400, 1000);
+        let (path, _) = deletion_candidate(&fs, &tight).unwrap().unwrap();
+        assert_eq!(path, "/a.b");
+        let set = deletion_set(&fs, &tight).unwrap().unwrap();
+        assert_eq!(set, [("/a.b".into(), 29700)]);
+
+        let fs = FileSystem::from_transcript(crate::day7::INPUT).unwrap();
+        let set = deletion_set(&fs, &Default::default()).unwrap().unwrap();
+        let freed = set.iter().map(|(_, size)| size).sum::<usize>();
+        assert_eq!(freed, 3837783);
+        for (a, _) in &set {
+            assert_eq!(set.iter().filter(|(b, _)| b.starts_with(a)).count(), 1);
+        }
+
+        // Sixty similar siblings would take the exhaustive search forever
+        let mut transcript = String::from("$ cd /\n$ ls\n");
+        for i in 0..60 {
+            writeln!(transcript, "dir s{i}").unwrap();
+        }
+        for i in 0..60 {
+            let size = 850_000 + (i * 7919) % 100_000;
+            write!(transcript, "$ cd /s{i}\n$ ls\n{size} f\n").unwrap();
+        }
+        let fs = FileSystem::from_transcript(&transcript).unwrap();
+        let (_, single) = deletion_candidate(&fs, &Default::default())
+            .unwrap()
+            .unwrap();
+        let set = deletion_set(&fs, &Default::default()).unwrap().unwrap();
+        let freed = set.iter().map(|(_, size)| size).sum::<usize>();
+        let used = fs.du(None).unwrap().last().unwrap().1;
+        assert!(freed >= used - 40_000_000 && freed <= single);
+    }
+
+    #[test]
+    fn revisits() {
+        let transcript =
+            format!("{TRANSCRIPT}$ cd /\n$ ls\ndir a.b\n14848514 b.txt\n$ cd a.b\n$ ls\n29116 f\n");
+        assert_eq!(crate::day7::solve_part1(TRANSCRIPT).unwrap(), 29700 + 584);
+        assert_eq!(crate::day7::solve_part1(&transcript).unwrap(), 29700 + 584);
+
+        let contradiction = format!("{TRANSCRIPT}$ ls\n29117 f\n");
+        assert!(FileSystem::from_transcript(&contradiction).is_err());
+        let contradiction = format!("{TRANSCRIPT}$ ls\ndir f\n");
+        assert!(FileSystem::from_transcript(&contradiction).is_err());
+
+        let into_file = "$ cd /\n$ ls\n5 a\n$ cd a\n$ ls\n3 b\ndir c\n";
+        let error = FileSystem::from_transcript(into_file).unwrap_err();
+        assert!(
+            format!("{error:#}").contains("a: Not a directory"),
+            "{error:#}"
+        );
+    }
+}
+
 test_day!(day8, Ok(1546), Ok(519064));
+
+mod day8_render {
+    use crate::day8::{best_spot, parse_input, render};
+
+    const FOREST: &str = "30373\n25512\n65332\n33549\n35390\n";
+
+    #[test]
+    fn best_spot_is_marked() {
+        let grid = parse_input(FOREST).unwrap();
+        assert_eq!(best_spot(&grid), Some(((3, 2), 8)));
+
+        for image in [render::visibility(&grid), render::heatmap(&grid)] {
+            assert_eq!((image.width, image.height), (5, 5));
+            assert_eq!(image.pixels[3 * 5 + 2], [230, 30, 30]);
+        }
+
+        // The upward ray stops at the first tree at least as tall as the treehouse
+        let image = render::visibility(&grid);
+        assert!(image.pixels[5 + 2][0] > 150);
+        assert_eq!(image.pixels[2], [53, 133, 53]);
+    }
+
+    #[test]
+    fn formats() {
+        let image = render::heatmap(&parse_input(FOREST).unwrap()).scaled(2);
+
+        let mut ppm = vec![];
+        image.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(ppm.len(), 13 + 10 * 10 * 3);
+
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        assert_eq!(image.to_ansi().lines().count(), 10);
+    }
+}
+
+mod day8_linear {
+    use proptest::strategy::Strategy;
+
+    use crate::day8::{best_scenic_score, count_visible, reference};
+
+    proptest::proptest! {
+        #[test]
+        fn agrees_with_reference(
+            grid in (1..15_usize).prop_flat_map(|n| {
+                proptest::collection::vec(proptest::collection::vec(0..10_u8, n), n)
+            })
+        ) {
+            proptest::prop_assert_eq!(count_visible(&grid), reference::count_visible(&grid));
+            proptest::prop_assert_eq!(
+                best_scenic_score(&grid),
+                reference::best_scenic_score(&grid)
+            );
+        }
+    }
+}
+
+mod day8_generate {
+    use proptest::{prop_oneof, strategy::Strategy};
+
+    use crate::day8::{
+        generate::{write_grid, Forest},
+        parse_input, reference, solve_part1, solve_part2,
+    };
+
+    fn forests() -> impl Strategy<Value = Forest> {
+        prop_oneof![
+            proptest::arbitrary::any::<u64>().prop_map(|seed| Forest::Random { seed }),
+            (0..10_u8).prop_map(Forest::Flat),
+            proptest::strategy::Just(Forest::Pyramid),
+            proptest::strategy::Just(Forest::Ramp),
+        ]
+    }
+
+    #[test]
+    fn adversarial() {
+        let pyramid = Forest::Pyramid.grow(5, 7);
+        assert_eq!(pyramid[2], [0, 1, 2, 2, 2, 1, 0]);
+        assert_eq!(reference::count_visible(&pyramid), 35);
+
+        let flat = Forest::Flat(4).grow(4, 6);
+        assert_eq!(reference::count_visible(&flat), 16);
+        assert_eq!(reference::best_scenic_score(&flat), Some(1));
+
+        assert_eq!(
+            Forest::Ramp.grow(1, 12)[0],
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1]
+        );
+        assert_eq!(
+            Forest::Random { seed: 7 }.grow(6, 3),
+            Forest::Random { seed: 7 }.grow(6, 3)
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn solvers_agree(forest in forests(), rows in 1..30_usize, cols in 1..30_usize) {
+            let grid = forest.grow(rows, cols);
+            let mut text = vec![];
+            write_grid(&grid, &mut text).unwrap();
+            let text = String::from_utf8(text).unwrap();
+
+            proptest::prop_assert_eq!(parse_input(&text).unwrap(), grid.clone());
+            proptest::prop_assert_eq!(
+                solve_part1(&text).unwrap(),
+                reference::count_visible(&grid)
+            );
+            proptest::prop_assert_eq!(
+                solve_part2(&text).ok(),
+                reference::best_scenic_score(&grid)
+            );
+        }
+    }
+}
 test_day!(day13, Ok(5825), Ok(24477));
+
+mod day13_packets {
+    use proptest::{arbitrary::any, strategy::Strategy};
+
+    use crate::day13::{parse_packet, Entry};
+
+    fn packet() -> impl Strategy<Value = Entry> {
+        any::<u64>()
+            .prop_map(Entry::Number)
+            .prop_recursive(4, 32, 6, |inner| {
+                proptest::collection::vec(inner, 0..6).prop_map(Entry::List)
+            })
+    }
+
+    #[test]
+    fn input_round_trips() {
+        for line in crate::day13::INPUT.lines().filter(|l| !l.trim().is_empty()) {
+            assert_eq!(parse_packet(line).unwrap().to_string(), line.trim_end());
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        for line in [
+            "[1,2",
+            "[1,,2]",
+            "[]]",
+            "[-1]",
+            "[18446744073709551616]",
+            "",
+        ] {
+            assert!(parse_packet(line).is_err(), "{line:?} parsed");
+        }
+    }
+
+    #[test]
+    fn explain() {
+        use std::cmp::Ordering;
+
+        use crate::day13::{explain, Outcome, Side};
+
+        let explain = |l, r| explain(&parse_packet(l).unwrap(), &parse_packet(r).unwrap());
+
+        let e = explain("[1,1,3,1,1]", "[1,1,5,1,1]");
+        assert_eq!(
+            (e.ordering, e.outcome),
+            (Ordering::Less, Outcome::Numbers(3, 5))
+        );
+        assert_eq!(e.to_string(), "[2] 3 vs 5");
+
+        let e = explain("[[1],[2,3,4]]", "[[1],4]");
+        assert_eq!(e.path, [1, 0]);
+        assert_eq!(e.promotions, [(vec![1], Side::Right)]);
+        assert_eq!(
+            e.to_string(),
+            "[1][0] 2 vs 4, right integer promoted at [1]"
+        );
+
+        let e = explain("[9]", "[[8,7,6]]");
+        assert_eq!(e.ordering, Ordering::Greater);
+        assert_eq!(e.to_string(), "[0][0] 9 vs 8, left integer promoted at [0]");
+
+        let e = explain("[[4,4],4,4]", "[[4,4],4,4,4]");
+        assert_eq!(e.to_string(), "top level left ran out of items");
+        assert_eq!(explain("[[]]", "[[]]").outcome, Outcome::Equal);
+    }
+
+    #[test]
+    fn ranks() {
+        use crate::day13::{decoder_key, ranks};
+
+        // Equal in order without being the same packet
+        let queries = [parse_packet("[2]").unwrap(), parse_packet("[[2]]").unwrap()];
+        assert_eq!(ranks(&[], &queries), [1, 2]);
+        assert_eq!(decoder_key("[1]\n[3]\n", &queries).unwrap(), 6);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn explain_agrees_with_ord(l in packet(), r in packet()) {
+            proptest::prop_assert_eq!(crate::day13::explain(&l, &r).ordering, l.cmp(&r));
+        }
+
+        #[test]
+        fn ranks_agree_with_sorting(
+            packets in proptest::collection::vec(packet(), 0..20),
+            queries in proptest::collection::vec(packet(), 0..4),
+        ) {
+            let mut sorted = packets
+                .iter()
+                .map(|p| (p, None))
+                .chain(queries.iter().enumerate().map(|(i, q)| (q, Some(i))))
+                .collect::<Vec<_>>();
+            sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let expected = (0..queries.len())
+                .map(|i| sorted.iter().position(|&(_, q)| q == Some(i)).unwrap() + 1)
+                .collect::<Vec<_>>();
+
+            proptest::prop_assert_eq!(crate::day13::ranks(&packets, &queries), expected);
+        }
+
+        #[test]
+        fn round_trip(packet in packet()) {
+            proptest::prop_assert_eq!(parse_packet(&packet.to_string()).unwrap(), packet);
+        }
+    }
+}
 test_day!(day14, Ok(805), Ok(25161));
 test_day!(day15, Ok(5403290), Ok(10291582906626));
 test_day!(day16, Ok(2087), Ok(2591));
+
+mod day14_grid {
+    use crate::day14::wasm::Grid;
+
+    const CAVE: &str = "498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9";
+
+    #[test]
+    fn run_to_completion() {
+        assert_eq!(Grid::parse(CAVE, true).unwrap().run_to_completion(), 93);
+    }
+
+    #[test]
+    fn reset() {
+        let mut grid = Grid::parse(CAVE, true).unwrap();
+        assert!(!grid.step_n(50));
+        assert!(grid.num_settled() > 0);
+        grid.reset();
+        assert_eq!((grid.num_settled(), grid.num_grains()), (0, 0));
+        assert_eq!(grid.run_to_completion(), 93);
+    }
+
+    #[test]
+    fn sized_from_input() {
+        assert_eq!(
+            Grid::parse(&format!("{CAVE}\n\n"), false)
+                .unwrap()
+                .run_to_completion(),
+            24
+        );
+        // Well outside where the floor used to end
+        let far = "100,5 -> 100,8\n900,2 -> 902,2";
+        assert_eq!(Grid::parse(far, false).unwrap().run_to_completion(), 0);
+        assert_eq!(Grid::parse(far, true).unwrap().run_to_completion(), 100);
+        assert_eq!(Grid::parse("\n", true).unwrap().run_to_completion(), 4);
+        // Piled up to the source without ever falling out
+        let cup = "490,0 -> 490,5 -> 510,5 -> 510,0";
+        assert_eq!(Grid::parse(cup, false).unwrap().run_to_completion(), 25);
+
+        assert!(Grid::parse("498,4 -> 500,6", true).is_err());
+        assert!(Grid::parse("498,4 -> 498,4", true).is_err());
+        assert!(Grid::parse("498,4 ->", true).is_err());
+        assert!(Grid::parse("0,0 -> 0,30000", true).is_err());
+    }
+}
+
+mod registry {
+    use crate::registry::{solve, DAYS};
+
+    #[test]
+    fn bad_input() {
+        for day in DAYS {
+            for part in 1..=2 {
+                for input in ["", "\n", "\n\n\n", "abc", "🦀\n", "1\n-2\n\n3", "1,2 -> x"] {
+                    // Anything but a panic
+                    let _ = solve(day.number, part, input);
+                }
+
+                let solved = solve(day.number, part, "🦀");
+                assert!(
+                    solved.is_err(),
+                    "Day {} part {part} solved a crab",
+                    day.number
+                );
+            }
+        }
+
+        for valves in [
+            "",
+            "Valve BB has flow rate=0; tunnel leads to valve AA",
+            "Valve AA has flow rate=0; tunnel leads to valve BB",
+        ] {
+            assert!(solve(16, 1, valves).is_err());
+        }
+        let stuck = "Valve AA has flow rate=0; tunnel leads to valve AA";
+        assert_eq!(solve(16, 2, stuck).unwrap().answer, "0");
+    }
+}
@@ -1,10 +1,12 @@
 use std::{
     cmp::{max, min},
-    ops::{Add, Range, RangeInclusive},
+    io::{self, BufRead},
+    iter, mem,
+    ops::RangeInclusive,
 };
 
 use easy_ext::ext;
-use num_traits::identities::One;
+use num_traits::{identities::One, CheckedAdd};
 
 #[macro_export]
 macro_rules! parse {
@@ -20,18 +22,55 @@ macro_rules! parse {
     };
 }
 
-#[ext(RangeExt)]
-pub impl<T> Range<T>
-where
-    T: Ord + Clone,
-{
-    fn contains_range(&self, other: &Range<T>) -> bool {
-        self.start <= other.start && self.end >= other.end
-    }
+/// Groups of consecutive non-blank lines, each with the line number (from 1) it starts on.
+/// Works with LF and CRLF endings, whitespace-only separators and a missing final newline.
+/// Records keep their indentation but lose any trailing whitespace.
+pub fn records(input: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    iter::from_generator(move || {
+        let mut start = None;
+        let (mut offset, mut end) = (0, 0);
+        for (i, line) in input.split_inclusive('\n').enumerate() {
+            if !line.trim().is_empty() {
+                start.get_or_insert((i + 1, offset));
+                end = offset + line.len();
+            } else if let Some((line_number, from)) = start.take() {
+                yield (line_number, input[from..end].trim_end());
+            }
+            offset += line.len();
+        }
+        if let Some((line_number, from)) = start {
+            yield (line_number, input[from..end].trim_end());
+        }
+    })
+}
 
-    fn overlaps(&self, other: &Range<T>) -> bool {
-        min(self.end.clone(), other.end.clone()) >= max(self.start.clone(), other.start.clone())
-    }
+/// [`records`] read one line at a time, for input too big to hold in memory at once.
+pub fn read_records(mut reader: impl BufRead) -> impl Iterator<Item = io::Result<(usize, String)>> {
+    iter::from_generator(move || {
+        let (mut line, mut record) = (String::new(), String::new());
+        let mut start = None;
+        for line_number in 1.. {
+            line.clear();
+            let read = match reader.read_line(&mut line) {
+                Ok(read) => read,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            if !line.trim().is_empty() {
+                start.get_or_insert(line_number);
+                record.push_str(&line);
+            } else if let Some(from) = start.take() {
+                record.truncate(record.trim_end().len());
+                yield Ok((from, mem::take(&mut record)));
+            }
+            if read == 0 {
+                break;
+            }
+        }
+    })
 }
 
 #[ext(InclusiveRangeExt)]
@@ -57,9 +96,8 @@ where
 
     fn precedes(&self, other: &RangeInclusive<T>) -> bool
     where
-        T: Add<T, Output = T>,
-        T: One,
+        T: CheckedAdd + One,
     {
-        self.end().clone() + T::one() == other.start().clone()
+        self.end().checked_add(&T::one()).as_ref() == Some(other.start())
     }
 }
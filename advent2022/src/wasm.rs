@@ -0,0 +1,52 @@
+use wasm_bindgen::prelude::*;
+
+use crate::registry;
+
+#[wasm_bindgen(js_name = Solution)]
+pub struct JsSolution {
+    answer: String,
+    elapsed_ms: f64,
+}
+
+#[wasm_bindgen(js_class = Solution)]
+impl JsSolution {
+    #[wasm_bindgen(getter)]
+    pub fn answer(&self) -> String {
+        self.answer.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+}
+
+/// Solves `part` of `day` against a pasted puzzle input.
+#[wasm_bindgen]
+pub fn solve(day: u32, part: u32, input: &str) -> Result<JsSolution, JsError> {
+    let solution =
+        registry::solve(day, part, input).map_err(|e| JsError::new(&format!("{e:#}")))?;
+
+    Ok(JsSolution {
+        answer: solution.answer,
+        elapsed_ms: solution.elapsed.as_secs_f64() * 1000.0,
+    })
+}
+
+#[wasm_bindgen]
+pub fn days() -> Vec<u32> {
+    registry::DAYS.iter().map(|day| day.number).collect()
+}
+
+#[wasm_bindgen]
+pub fn description(day: u32, part: u32) -> Option<String> {
+    let part = registry::day(day)?
+        .parts
+        .get(part.checked_sub(1)? as usize)?;
+    Some(part.description.to_string())
+}
+
+#[wasm_bindgen]
+pub fn default_input(day: u32) -> Option<String> {
+    registry::day(day).map(|day| day.input.to_string())
+}